@@ -0,0 +1,93 @@
+use bech32::{u5, ToBase32, Variant};
+use curv::elliptic::curves::{Point, Secp256k1};
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::{error::LindellError, keccak256};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BtcNetwork {
+    Mainnet,
+    Testnet,
+}
+
+impl BtcNetwork {
+    fn p2pkh_version(self) -> u8 {
+        match self {
+            BtcNetwork::Mainnet => 0x00,
+            BtcNetwork::Testnet => 0x6f,
+        }
+    }
+
+    fn bech32_hrp(self) -> &'static str {
+        match self {
+            BtcNetwork::Mainnet => "bc",
+            BtcNetwork::Testnet => "tb",
+        }
+    }
+}
+
+/// 33-byte SEC1 compressed encoding.
+pub fn public_key_compressed(public: &Point<Secp256k1>) -> Vec<u8> {
+    public.to_bytes(true).to_vec()
+}
+
+/// 65-byte SEC1 uncompressed encoding, including the `0x04` prefix.
+pub fn public_key_uncompressed(public: &Point<Secp256k1>) -> Vec<u8> {
+    public.to_bytes(false).to_vec()
+}
+
+/// Raw 20-byte Ethereum address, `keccak256(x || y)[12..]`.
+pub fn eth_address(public: &Point<Secp256k1>) -> [u8; 20] {
+    let uncompressed = public_key_uncompressed(public);
+    let hash = keccak256(&uncompressed[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    address
+}
+
+/// EIP-55 checksummed Ethereum address with `0x` prefix.
+pub fn eth_checksum_address(public: &Point<Secp256k1>) -> String {
+    let address = hex::encode(eth_address(public));
+    let hash = keccak256(address.as_bytes());
+    let checksummed: String = address
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+/// `ripemd160(sha256(compressed pubkey))`.
+pub fn hash160(public: &Point<Secp256k1>) -> [u8; 20] {
+    let sha = Sha256::digest(&public_key_compressed(public));
+    let mut out = [0u8; 20];
+    out.copy_from_slice(&Ripemd160::digest(&sha));
+    out
+}
+
+/// Base58check P2PKH address of the compressed public key.
+pub fn btc_p2pkh_address(public: &Point<Secp256k1>, network: BtcNetwork) -> String {
+    let mut payload = vec![network.p2pkh_version()];
+    payload.extend_from_slice(&hash160(public));
+    bs58::encode(payload).with_check().into_string()
+}
+
+/// Bech32 witness v0 P2WPKH address of the compressed public key.
+pub fn btc_p2wpkh_address(
+    public: &Point<Secp256k1>,
+    network: BtcNetwork,
+) -> Result<String, LindellError> {
+    let mut data =
+        vec![u5::try_from_u8(0).map_err(|e| LindellError::SpecificError(e.to_string()))?];
+    data.extend(hash160(public).to_base32());
+    bech32::encode(network.bech32_hrp(), data, Variant::Bech32)
+        .map_err(|e| LindellError::SpecificError(e.to_string()))
+}
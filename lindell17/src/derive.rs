@@ -0,0 +1,123 @@
+use curv::{
+    arithmetic::traits::*,
+    elliptic::curves::{Point, Scalar, Secp256k1},
+    BigInt,
+};
+use hmac::{Hmac, Mac, NewMac};
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_two;
+use sha2::Sha512;
+
+use crate::{
+    error::LindellError,
+    keygen::{Li17SignP1Context, Li17SignP2Context},
+    li17_p2_exract_secret,
+};
+
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// Non-hardened BIP32 public derivation (CKDpub) along `path`.
+///
+/// Returns the child public key, the accumulated additive tweak
+/// (`child = parent + tweak * G`) and the child chain code.
+pub fn derive_child_public(
+    public: &Point<Secp256k1>,
+    chain_code: &[u8],
+    path: &[u32],
+) -> Result<(Point<Secp256k1>, Scalar<Secp256k1>, Vec<u8>), LindellError> {
+    if chain_code.len() != 32 {
+        return Err(LindellError::DeriveError(
+            "chain code must be 32 bytes".to_string(),
+        ));
+    }
+    let mut public = public.clone();
+    let mut chain_code = chain_code.to_vec();
+    let mut tweak = Scalar::<Secp256k1>::zero();
+
+    for &index in path {
+        if index >= HARDENED_OFFSET {
+            return Err(LindellError::DeriveError(
+                "hardened derivation is not supported".to_string(),
+            ));
+        }
+        let mut mac = Hmac::<Sha512>::new_from_slice(&chain_code)
+            .map_err(|e| LindellError::DeriveError(e.to_string()))?;
+        mac.update(&*public.to_bytes(true));
+        mac.update(&index.to_be_bytes());
+        let i = mac.finalize().into_bytes();
+
+        let il = BigInt::from_bytes(&i[..32]);
+        if &il >= Scalar::<Secp256k1>::group_order() {
+            return Err(LindellError::DeriveError("invalid child index".to_string()));
+        }
+        let il = Scalar::<Secp256k1>::from(&il);
+        public = public + Point::generator() * &il;
+        if public.is_zero() {
+            return Err(LindellError::DeriveError("invalid child index".to_string()));
+        }
+        tweak = tweak + il;
+        chain_code = i[32..].to_vec();
+    }
+    Ok((public, tweak, chain_code))
+}
+
+/// Derives P1's child signing context. P1's Paillier key and `x1` are left
+/// untouched, only the joint public key moves; the tweak is absorbed by P2.
+///
+/// Child contexts sign like any other context but cannot be refreshed:
+/// refresh the root pair and derive again instead.
+pub fn li17_p1_derive_child(
+    context: &Li17SignP1Context,
+    path: &[u32],
+) -> Result<Li17SignP1Context, LindellError> {
+    let (public, _, chain_code) = derive_child_public(&context.public, &context.chain_code, path)?;
+    Ok(Li17SignP1Context {
+        public,
+        public_p1: context.public_p1.clone(),
+        public_p2: context.public_p2.clone(),
+        p1_private: context.p1_private.clone(),
+        chain_code,
+        path: [&context.path[..], path].concat(),
+        presignatures_issued: context.presignatures_issued,
+        presignature_high_water: context.presignature_high_water,
+        tainted: context.tainted,
+    })
+}
+
+/// Derives P2's child signing context by homomorphically adding
+/// `tweak * x2^-1` to P1's encrypted share, so that `x1' * x2 = x1 * x2 + tweak`.
+pub fn li17_p2_derive_child(
+    context: &Li17SignP2Context,
+    path: &[u32],
+) -> Result<Li17SignP2Context, LindellError> {
+    let (public, tweak, chain_code) =
+        derive_child_public(&context.public, &context.chain_code, path)?;
+
+    let x2 = li17_p2_exract_secret(context)?;
+    let x2_inv = x2
+        .invert()
+        .ok_or_else(|| LindellError::DeriveError("zero secret share".to_string()))?;
+    let delta = (tweak * x2_inv).to_bigint();
+
+    // Enc(x1) * (1 + N)^delta = Enc(x1 + delta)
+    let ek = context.p2_paillier_public.ek.clone();
+    let encrypted_secret_share = BigInt::mod_mul(
+        &context.p2_paillier_public.encrypted_secret_share,
+        &(BigInt::from(1) + &delta * &ek.n),
+        &ek.nn,
+    );
+
+    let p2_paillier_public = party_two::PaillierPublic {
+        ek,
+        encrypted_secret_share,
+    };
+    Ok(Li17SignP2Context {
+        public,
+        public_p1: context.public_p1.clone(),
+        public_p2: context.public_p2.clone(),
+        p2_private: serde_json::from_value(serde_json::to_value(&context.p2_private)?)?,
+        p2_paillier_public,
+        chain_code,
+        path: [&context.path[..], path].concat(),
+        presignature_high_water: context.presignature_high_water,
+    })
+}
@@ -0,0 +1,248 @@
+//! EIP-712 typed structured data hashing.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use curv::{arithmetic::traits::*, BigInt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{error::LindellError, keccak256};
+
+const EIP712_DOMAIN: &str = "EIP712Domain";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+/// The `eth_signTypedData_v4` JSON payload.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    pub primary_type: String,
+    pub domain: Value,
+    pub message: Value,
+}
+
+impl TypedData {
+    pub fn from_json(json: &str) -> Result<Self, LindellError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    fn fields(&self, type_name: &str) -> Result<&Vec<TypedDataField>, LindellError> {
+        self.types
+            .get(type_name)
+            .ok_or_else(|| typed_data_error(format!("unknown type {}", type_name)))
+    }
+
+    fn collect_dependencies(
+        &self,
+        type_name: &str,
+        found: &mut BTreeSet<String>,
+    ) -> Result<(), LindellError> {
+        if found.contains(type_name) {
+            return Ok(());
+        }
+        found.insert(type_name.to_string());
+        for field in self.fields(type_name)? {
+            let base = base_type(&field.r#type);
+            if self.types.contains_key(base) {
+                self.collect_dependencies(base, found)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `encodeType`: the primary type followed by its dependencies sorted by name.
+    pub fn encode_type(&self, type_name: &str) -> Result<String, LindellError> {
+        let mut deps = BTreeSet::new();
+        self.collect_dependencies(type_name, &mut deps)?;
+        deps.remove(type_name);
+
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(deps.iter().map(|s| s.as_str())) {
+            let fields = self
+                .fields(name)?
+                .iter()
+                .map(|f| format!("{} {}", f.r#type, f.name))
+                .collect::<Vec<_>>()
+                .join(",");
+            encoded.push_str(&format!("{}({})", name, fields));
+        }
+        Ok(encoded)
+    }
+
+    pub fn type_hash(&self, type_name: &str) -> Result<Vec<u8>, LindellError> {
+        Ok(keccak256(self.encode_type(type_name)?.as_bytes()))
+    }
+
+    /// `hashStruct(s) = keccak256(typeHash || encodeData(s))`.
+    pub fn hash_struct(&self, type_name: &str, value: &Value) -> Result<Vec<u8>, LindellError> {
+        let object = value
+            .as_object()
+            .ok_or_else(|| typed_data_error(format!("{} value is not an object", type_name)))?;
+
+        let mut encoded = self.type_hash(type_name)?;
+        for field in self.fields(type_name)? {
+            let field_value = object.get(&field.name).unwrap_or(&Value::Null);
+            encoded.extend(self.encode_value(&field.r#type, field_value)?);
+        }
+        Ok(keccak256(&encoded))
+    }
+
+    pub fn domain_separator(&self) -> Result<Vec<u8>, LindellError> {
+        self.hash_struct(EIP712_DOMAIN, &self.domain)
+    }
+
+    fn encode_value(&self, type_name: &str, value: &Value) -> Result<Vec<u8>, LindellError> {
+        if type_name.ends_with(']') {
+            let inner = &type_name[..type_name.rfind('[').unwrap_or(0)];
+            let items = value
+                .as_array()
+                .ok_or_else(|| typed_data_error(format!("{} value is not an array", type_name)))?;
+            let mut encoded = vec![];
+            for item in items {
+                encoded.extend(self.encode_value(inner, item)?);
+            }
+            return Ok(keccak256(&encoded));
+        }
+
+        if self.types.contains_key(type_name) {
+            return self.hash_struct(type_name, value);
+        }
+
+        match type_name {
+            "string" => {
+                let s = value
+                    .as_str()
+                    .ok_or_else(|| typed_data_error("string value expected".to_string()))?;
+                Ok(keccak256(s.as_bytes()))
+            }
+            "bytes" => Ok(keccak256(&parse_hex(value)?)),
+            "bool" => {
+                let b = value
+                    .as_bool()
+                    .ok_or_else(|| typed_data_error("bool value expected".to_string()))?;
+                Ok(left_pad32(&[b as u8]))
+            }
+            "address" => {
+                let bytes = parse_hex(value)?;
+                if bytes.len() != 20 {
+                    return Err(typed_data_error("address must be 20 bytes".to_string()));
+                }
+                Ok(left_pad32(&bytes))
+            }
+            _ if type_name.starts_with("bytes") => {
+                let bytes = parse_hex(value)?;
+                let size: usize = type_name[5..]
+                    .parse()
+                    .map_err(|_| typed_data_error(format!("unknown type {}", type_name)))?;
+                if size == 0 || size > 32 || bytes.len() != size {
+                    return Err(typed_data_error(format!("bad {} value", type_name)));
+                }
+                let mut out = bytes;
+                out.resize(32, 0);
+                Ok(out)
+            }
+            _ if type_name.starts_with("uint") || type_name.starts_with("int") => {
+                encode_integer(type_name, value)
+            }
+            _ => Err(typed_data_error(format!("unknown type {}", type_name))),
+        }
+    }
+}
+
+fn typed_data_error(msg: String) -> LindellError {
+    LindellError::InvalidTypedData(msg)
+}
+
+fn base_type(type_name: &str) -> &str {
+    match type_name.find('[') {
+        Some(index) => &type_name[..index],
+        None => type_name,
+    }
+}
+
+fn left_pad32(bytes: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; 32 - bytes.len()];
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn parse_hex(value: &Value) -> Result<Vec<u8>, LindellError> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| typed_data_error("hex string expected".to_string()))?;
+    hex::decode(s.trim_start_matches("0x")).map_err(|e| typed_data_error(e.to_string()))
+}
+
+fn encode_integer(type_name: &str, value: &Value) -> Result<Vec<u8>, LindellError> {
+    let signed = type_name.starts_with("int");
+    let bits: usize = type_name[if signed { 3 } else { 4 }..]
+        .parse()
+        .map_err(|_| typed_data_error(format!("unknown type {}", type_name)))?;
+    if bits == 0 || bits > 256 || bits % 8 != 0 {
+        return Err(typed_data_error(format!("unknown type {}", type_name)));
+    }
+
+    let n = match value {
+        Value::Number(n) => {
+            let s = n.to_string();
+            BigInt::from_str_radix(&s, 10)
+                .map_err(|_| typed_data_error(format!("bad {} value", type_name)))?
+        }
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s.as_str()),
+            };
+            let n = match digits.strip_prefix("0x") {
+                Some(hex) => BigInt::from_str_radix(hex, 16),
+                None => BigInt::from_str_radix(digits, 10),
+            }
+            .map_err(|_| typed_data_error(format!("bad {} value", type_name)))?;
+            if negative {
+                BigInt::from(0) - n
+            } else {
+                n
+            }
+        }
+        _ => return Err(typed_data_error(format!("bad {} value", type_name))),
+    };
+
+    let zero = BigInt::from(0);
+    let (min, max) = if signed {
+        let half = BigInt::from(2).pow((bits - 1) as u32);
+        (BigInt::from(0) - &half, half - BigInt::from(1))
+    } else {
+        (
+            zero.clone(),
+            BigInt::from(2).pow(bits as u32) - BigInt::from(1),
+        )
+    };
+    if n < min || n > max {
+        return Err(typed_data_error(format!(
+            "{} value out of range",
+            type_name
+        )));
+    }
+
+    let n = if n < zero {
+        BigInt::from(2).pow(256) + n
+    } else {
+        n
+    };
+    Ok(left_pad32(&n.to_bytes()))
+}
+
+/// `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`, ready to be
+/// passed as the message hash to `li17_p1_sign1` / `li17_p2_sign1`.
+pub fn hash_typed_data(typed_data: &TypedData) -> Result<Vec<u8>, LindellError> {
+    let mut msg = vec![0x19, 0x01];
+    msg.extend(typed_data.domain_separator()?);
+    msg.extend(typed_data.hash_struct(&typed_data.primary_type, &typed_data.message)?);
+    Ok(keccak256(&msg))
+}
@@ -17,6 +17,70 @@ pub enum LindellError {
     #[error("error:`{0:?}`")]
     KeyGenError(multi_party_ecdsa::Error),
 
+    #[error("invalid paillier key proof")]
+    InvalidPaillierKeyProof,
+
+    #[error("invalid pdl proof")]
+    InvalidPdlProof,
+
+    #[error("paillier modulus too small: {0} bits")]
+    PaillierModulusTooSmall(usize),
+
+    #[error("paillier modulus too large: {0} bits")]
+    PaillierModulusTooLarge(usize),
+
+    #[error("invalid paillier-blum modulus proof")]
+    InvalidPaillierBlumProof,
+
+    #[error("invalid no small factor proof")]
+    InvalidNoSmallFactorProof,
+
+    #[error("invalid ring-pedersen parameters proof")]
+    InvalidRingPedersenProof,
+
+    #[error("ring-pedersen modulus too small: {0} bits")]
+    RingPedersenModulusTooSmall(usize),
+
+    #[error("ring-pedersen modulus too large: {0} bits")]
+    RingPedersenModulusTooLarge(usize),
+
+    #[error("invalid signature encoding: `{0}`")]
+    InvalidSignatureEncoding(String),
+
+    #[error("invalid typed data: `{0}`")]
+    InvalidTypedData(String),
+
+    #[error("derive error: `{0}`")]
+    DeriveError(String),
+
+    #[error("presignature `{0}` already used")]
+    PresignatureUsed(String),
+
+    #[error("presignature `{0}` not found")]
+    PresignatureNotFound(String),
+
+    #[error("presignature id mismatch")]
+    PresignatureMismatch,
+
+    #[error("batch messages do not match the batch items")]
+    BatchLengthMismatch,
+
+    /// Every failed item of a batch, by index.
+    #[error("batch items failed: {}", describe_batch_errors(.0))]
+    BatchItemErrors(Vec<(usize, LindellError)>),
+
+    #[error("session id mismatch")]
+    SessionIdMismatch,
+
+    #[error("message hash mismatch")]
+    MessageHashMismatch,
+
+    #[error("signature failed to verify, key share is tainted and needs a fresh keygen")]
+    SignatureAbort,
+
+    #[error("key share is tainted, a fresh keygen is required")]
+    KeyTainted,
+
     #[error(transparent)]
     PartyTwoError(#[from] PartyTwoError),
 
@@ -32,3 +96,11 @@ pub enum LindellError {
     #[error(transparent)]
     PointFromBytesError(#[from] curv::elliptic::curves::PointFromBytesError),
 }
+
+fn describe_batch_errors(errors: &[(usize, LindellError)]) -> String {
+    errors
+        .iter()
+        .map(|(index, err)| format!("{}: {}", index, err))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,4 +1,5 @@
 use curv::{
+    arithmetic::traits::*,
     elliptic::curves::{Point, Scalar, Secp256k1},
     BigInt,
 };
@@ -8,9 +9,16 @@ use multi_party_ecdsa::{
 };
 use paillier::EncryptionKey;
 use serde::{Deserialize, Serialize};
-use zk_paillier::zkproofs::{CompositeDLogProof, NiCorrectKeyProof};
+use sha2::{Digest, Sha256};
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NiCorrectKeyProof};
 
-use crate::error::LindellError;
+use crate::{
+    error::LindellError,
+    modulus::{
+        li17_p1_paillier_key_pair, ring_pedersen_setup, verify_paillier_modulus,
+        verify_ring_pedersen, NoSmallFactorProof, PaillierBlumModulusProof, RingPedersenProof,
+    },
+};
 
 // party one structures
 #[derive(Clone, Serialize, Deserialize)]
@@ -27,6 +35,28 @@ pub struct Li17SignP1Context {
     pub public_p1: Point<Secp256k1>,
     pub public_p2: Point<Secp256k1>,
     pub p1_private: party_one::Party1Private,
+    #[serde(default)]
+    pub chain_code: Vec<u8>,
+    /// Derivation path from the root key, empty for the root itself.
+    /// `public_p1` and `public_p2` are always the root key's shares.
+    #[serde(default)]
+    pub path: Vec<u32>,
+    /// Sequence number of the last presignature made with this key.
+    #[serde(default)]
+    pub presignatures_issued: u64,
+    /// Sequence number of the last presignature this key has signed with.
+    /// Presignatures are used in the order they were made, so a copied or
+    /// older one is refused without remembering every id.
+    #[serde(default)]
+    pub presignature_high_water: u64,
+    /// Set once a signature fails to verify, which may be a selective-failure
+    /// attack by P2. A tainted context refuses to sign and to refresh: P2
+    /// knows the refresh factor, so the leaked bits carry over to the new
+    /// share and only a fresh keygen gets rid of them.
+    /// Signatures for derived keys are finished with the root context, so the
+    /// root's flag covers every key derived from it.
+    #[serde(default)]
+    pub tainted: bool,
 }
 
 pub type Li17KeyGenP1Msg2 = (
@@ -37,6 +67,8 @@ pub type Li17KeyGenP1Msg2 = (
     CompositeDLogProof,
     EncryptionKey,
     BigInt,
+    PaillierBlumModulusProof,
+    NoSmallFactorProof,
 );
 
 //party two structures
@@ -44,9 +76,17 @@ pub type Li17KeyGenP1Msg2 = (
 pub struct Li17KeyGenP2Context1 {
     p2_msg1_from_p1: party_one::KeyGenFirstMsg,
     p2_ec_key_pair: party_two::EcKeyPair,
+    ring_pedersen: DLogStatement,
 }
 
-pub type Li17KeyGenP2Msg1 = party_two::KeyGenFirstMsg;
+/// P2's key share message, plus the Ring-Pedersen parameters P1 proves
+/// its Paillier modulus against.
+#[derive(Serialize, Deserialize)]
+pub struct Li17KeyGenP2Msg1 {
+    pub first_msg: party_two::KeyGenFirstMsg,
+    pub ring_pedersen: DLogStatement,
+    pub ring_pedersen_proof: RingPedersenProof,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Li17SignP2Context {
@@ -55,10 +95,31 @@ pub struct Li17SignP2Context {
     pub public_p2: Point<Secp256k1>,
     pub p2_private: party_two::Party2Private,
     pub p2_paillier_public: party_two::PaillierPublic,
+    #[serde(default)]
+    pub chain_code: Vec<u8>,
+    /// Derivation path from the root key, empty for the root itself.
+    /// `public_p1` and `public_p2` are always the root key's shares.
+    #[serde(default)]
+    pub path: Vec<u32>,
+    /// Sequence number of the last presignature this key has signed with,
+    /// see `Li17SignP1Context::presignature_high_water`.
+    #[serde(default)]
+    pub presignature_high_water: u64,
 }
 
 pub type Li17KeyGenP2Msg2 = Point<Secp256k1>;
 
+// The BIP32 chain code is bound to both public shares and to the blind
+// factor P1 committed to before seeing P2's share, so neither party can bias it.
+fn chain_code(comm_witness: &party_one::CommWitness, public_p2: &Point<Secp256k1>) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"li17 chain code");
+    hasher.update(&comm_witness.pk_commitment_blind_factor.to_bytes());
+    hasher.update(&*comm_witness.public_share.to_bytes(true));
+    hasher.update(&*public_p2.to_bytes(true));
+    hasher.finalize().to_vec()
+}
+
 // party one functions
 
 pub fn li17_p1_key_gen1() -> Result<(Li17KeyGenP1Context1, Li17KeyGenP1Msg1), LindellError> {
@@ -87,11 +148,13 @@ pub fn li17_p1_key_gen2(
     context: Li17KeyGenP1Context1,
     msg: Li17KeyGenP2Msg1,
 ) -> Result<(Li17SignP1Context, Li17KeyGenP1Msg2), LindellError> {
-    let p1_second_message =
-        party_one::KeyGenSecondMsg::verify_and_decommit(context.p1_comm_witness, &msg.d_log_proof)?;
+    verify_ring_pedersen(&msg.ring_pedersen, &msg.ring_pedersen_proof)?;
+    let p1_second_message = party_one::KeyGenSecondMsg::verify_and_decommit(
+        context.p1_comm_witness,
+        &msg.first_msg.d_log_proof,
+    )?;
 
-    let paillier_key_pair =
-        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&context.p1_ec_key_pair);
+    let (paillier_key_pair, paillier_dk) = li17_p1_paillier_key_pair(&context.p1_ec_key_pair)?;
     let party_one_private =
         party_one::Party1Private::set_private_key(&context.p1_ec_key_pair, &paillier_key_pair);
 
@@ -101,16 +164,23 @@ pub fn li17_p1_key_gen2(
     let (pdl_statement, pdl_proof, composite_dlog_proof) =
         party_one::PaillierKeyPair::pdl_proof(&party_one_private, &paillier_key_pair);
     let ek = paillier_key_pair.ek.clone();
+    let blum_proof = PaillierBlumModulusProof::prove(&ek, &paillier_dk)?;
+    let no_small_factor_proof = NoSmallFactorProof::prove(&ek, &paillier_dk, &msg.ring_pedersen)?;
     let encrypted_share = paillier_key_pair.encrypted_share.clone();
 
     let party_one_private =
         party_one::Party1Private::set_private_key(&context.p1_ec_key_pair, &paillier_key_pair);
-    let public_key = party_one::compute_pubkey(&party_one_private, &msg.public_share);
+    let public_key = party_one::compute_pubkey(&party_one_private, &msg.first_msg.public_share);
     let sign_context = Li17SignP1Context {
         public: public_key.clone(),
         public_p1: context.p1_ec_key_pair.public_share,
-        public_p2: msg.public_share,
+        chain_code: chain_code(&p1_second_message.comm_witness, &msg.first_msg.public_share),
+        public_p2: msg.first_msg.public_share,
         p1_private: party_one_private,
+        path: vec![],
+        presignatures_issued: 0,
+        presignature_high_water: 0,
+        tainted: false,
     };
     let p1_msg2_for_p2 = (
         p1_second_message,
@@ -120,6 +190,8 @@ pub fn li17_p1_key_gen2(
         composite_dlog_proof,
         ek,
         encrypted_share,
+        blum_proof,
+        no_small_factor_proof,
     );
     Ok((sign_context, p1_msg2_for_p2))
 }
@@ -129,11 +201,18 @@ pub fn li17_p2_key_gen1(
     msg: Li17KeyGenP1Msg1,
 ) -> Result<(Li17KeyGenP2Context1, Li17KeyGenP2Msg1), LindellError> {
     let (p2_first_message, p2_ec_key_pair) = party_two::KeyGenFirstMsg::create();
+    let (ring_pedersen, ring_pedersen_proof) = ring_pedersen_setup();
     let context2 = Li17KeyGenP2Context1 {
         p2_msg1_from_p1: msg,
         p2_ec_key_pair,
+        ring_pedersen: ring_pedersen.clone(),
     };
-    Ok((context2, p2_first_message))
+    let p2_msg1 = Li17KeyGenP2Msg1 {
+        first_msg: p2_first_message,
+        ring_pedersen,
+        ring_pedersen_proof,
+    };
+    Ok((context2, p2_msg1))
 }
 
 pub fn li17_p2_key_gen1_with_fixed_secret_share(
@@ -142,11 +221,18 @@ pub fn li17_p2_key_gen1_with_fixed_secret_share(
 ) -> Result<(Li17KeyGenP2Context1, Li17KeyGenP2Msg1), LindellError> {
     let (p2_first_message, p2_ec_key_pair) =
         party_two::KeyGenFirstMsg::create_with_fixed_secret_share(secret_share);
+    let (ring_pedersen, ring_pedersen_proof) = ring_pedersen_setup();
     let context2 = Li17KeyGenP2Context1 {
         p2_msg1_from_p1: msg,
         p2_ec_key_pair,
+        ring_pedersen: ring_pedersen.clone(),
+    };
+    let p2_msg1 = Li17KeyGenP2Msg1 {
+        first_msg: p2_first_message,
+        ring_pedersen,
+        ring_pedersen_proof,
     };
-    Ok((context2, p2_first_message))
+    Ok((context2, p2_msg1))
 }
 
 pub fn li17_p2_key_gen2(
@@ -161,6 +247,8 @@ pub fn li17_p2_key_gen2(
         composite_dlog_proof,
         paillier_ek,
         paillier_encrypted_share,
+        blum_proof,
+        no_small_factor_proof,
     ) = msg;
 
     party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
@@ -177,7 +265,14 @@ pub fn li17_p2_key_gen2(
         correct_key_proof,
         &party_two_paillier.ek,
     )
-    .expect("bad paillier key");
+    .map_err(|_| LindellError::InvalidPaillierKeyProof)?;
+
+    verify_paillier_modulus(
+        &party_two_paillier.ek,
+        &blum_proof,
+        &no_small_factor_proof,
+        &context.ring_pedersen,
+    )?;
 
     party_two::PaillierPublic::pdl_verify(
         &composite_dlog_proof,
@@ -186,7 +281,7 @@ pub fn li17_p2_key_gen2(
         &party_two_paillier,
         &party_one_second_message.comm_witness.public_share,
     )
-    .expect("PDL error");
+    .map_err(|_| LindellError::InvalidPdlProof)?;
 
     let party_two_private = party_two::Party2Private::set_private_key(&context.p2_ec_key_pair);
     let public_key = party_two::compute_pubkey(
@@ -194,12 +289,19 @@ pub fn li17_p2_key_gen2(
         &party_one_second_message.comm_witness.public_share,
     );
 
+    let chain_code = chain_code(
+        &party_one_second_message.comm_witness,
+        &context.p2_ec_key_pair.public_share,
+    );
     let sign_context = Li17SignP2Context {
         public: public_key.clone(),
         public_p1: party_one_second_message.comm_witness.public_share,
         public_p2: context.p2_ec_key_pair.public_share,
         p2_private: party_two_private,
         p2_paillier_public: party_two_paillier,
+        chain_code,
+        path: vec![],
+        presignature_high_water: 0,
     };
 
     Ok((sign_context, public_key))
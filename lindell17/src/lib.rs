@@ -1,9 +1,11 @@
 use curv::{
+    arithmetic::traits::*,
     elliptic::curves::{Point, Scalar, Secp256k1},
     BigInt,
 };
 use error::LindellError;
 use keygen::{Li17SignP1Context, Li17SignP2Context};
+use modulus::{verify_paillier_modulus, NoSmallFactorProof, PaillierBlumModulusProof};
 use multi_party_ecdsa::{
     protocols::two_party_ecdsa::lindell_2017::party_one::generate_h1_h2_n_tilde,
     utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness},
@@ -24,9 +26,16 @@ pub struct Party1Private {
     c_key_randomness: BigInt,
 }
 
+pub mod address;
+pub mod derive;
+pub mod eip712;
 pub mod error;
 pub mod keygen;
+pub mod modulus;
+pub mod presign;
+pub mod refresh;
 pub mod sign;
+pub mod signature;
 pub mod tests;
 
 const ETHPREFIX: &[u8; 26] = b"\x19Ethereum Signed Message:\n";
@@ -58,12 +67,27 @@ pub fn li17_p2_exract_secret(
     return Ok(p2_private.x2);
 }
 
+/// `ring_pedersen` must come from the verifier, see `modulus::ring_pedersen_setup`.
 pub fn pdl_proof(
     x: Scalar<Secp256k1>,
     c_key_randomness: BigInt,
     ek: EncryptionKey,
+    dk: &DecryptionKey,
     encrypted_secret_share: BigInt,
-) -> (PDLwSlackStatement, PDLwSlackProof, CompositeDLogProof) {
+    ring_pedersen: &DLogStatement,
+) -> Result<
+    (
+        PDLwSlackStatement,
+        PDLwSlackProof,
+        CompositeDLogProof,
+        PaillierBlumModulusProof,
+        NoSmallFactorProof,
+    ),
+    LindellError,
+> {
+    let blum_proof = PaillierBlumModulusProof::prove(&ek, dk)?;
+    let no_small_factor_proof = NoSmallFactorProof::prove(&ek, dk, ring_pedersen)?;
+
     let (n_tilde, h1, h2, xhi) = generate_h1_h2_n_tilde();
     let dlog_statement = DLogStatement {
         N: n_tilde,
@@ -89,26 +113,32 @@ pub fn pdl_proof(
     };
 
     let pdl_w_slack_proof = PDLwSlackProof::prove(&pdl_w_slack_witness, &pdl_w_slack_statement);
-    (
+    Ok((
         pdl_w_slack_statement,
         pdl_w_slack_proof,
         composite_dlog_proof,
-    )
+        blum_proof,
+        no_small_factor_proof,
+    ))
 }
 
 pub fn pdl_verify(
     composite_dlog_proof: &CompositeDLogProof,
     pdl_w_slack_statement: &PDLwSlackStatement,
     pdl_w_slack_proof: &PDLwSlackProof,
+    blum_proof: &PaillierBlumModulusProof,
+    no_small_factor_proof: &NoSmallFactorProof,
     ek: EncryptionKey,
     encrypted_secret_share: BigInt,
     q: &Point<Secp256k1>,
+    ring_pedersen: &DLogStatement,
 ) -> Result<(), LindellError> {
+    verify_paillier_modulus(&ek, blum_proof, no_small_factor_proof, ring_pedersen)?;
     if pdl_w_slack_statement.ek != ek
         || pdl_w_slack_statement.ciphertext != encrypted_secret_share
         || &pdl_w_slack_statement.Q != q
     {
-        return Err(LindellError::SpecificError("pdl verify failed".to_string()));
+        return Err(LindellError::InvalidPdlProof);
     }
     let dlog_statement = DLogStatement {
         N: pdl_w_slack_statement.N_tilde.clone(),
@@ -120,6 +150,6 @@ pub fn pdl_verify(
     {
         Ok(())
     } else {
-        return Err(LindellError::SpecificError("pdl verify failed".to_string()));
+        return Err(LindellError::InvalidPdlProof);
     }
 }
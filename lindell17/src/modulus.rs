@@ -0,0 +1,573 @@
+//! Paillier modulus proofs from CGGMP21: the Paillier-Blum modulus proof
+//! (Π^mod), the no small factor proof (Π^fac) and the Ring-Pedersen
+//! parameters proof (Π^prm).
+
+//!
+//! The wasm bindings compile this same file against their own forks of the
+//! dependencies, so it only names its siblings through `super`, and the
+//! bindings supply a `super::error::LindellError` with the variants used here.
+
+use curv::{
+    arithmetic::traits::*,
+    elliptic::curves::{Scalar, Secp256k1},
+    BigInt,
+};
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one;
+use paillier::{
+    DecryptionKey, EncryptWithChosenRandomness, EncryptionKey, Keypair, Paillier, Randomness,
+    RawPlaintext,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zk_paillier::zkproofs::DLogStatement;
+
+use super::error::LindellError;
+
+/// Minimum accepted Paillier modulus size in bits.
+pub const PAILLIER_MIN_BITS: usize = 2048;
+/// Maximum accepted Paillier modulus size in bits, so that the other party
+/// cannot make every later operation arbitrarily slow.
+pub const PAILLIER_MAX_BITS: usize = 2 * PAILLIER_MIN_BITS;
+
+const BLUM_ITERATIONS: usize = 80;
+const RING_PEDERSEN_ITERATIONS: usize = 80;
+// statistical parameters ℓ and ε of Π^fac
+const FAC_L: usize = 256;
+const FAC_EPSILON: usize = 512;
+
+/// Samples a prime of exactly `bits` bits with `p = 3 mod 4`.
+fn sample_blum_prime(bits: usize) -> BigInt {
+    let four = BigInt::from(4);
+    let three = BigInt::from(3);
+    loop {
+        let mut candidate = BigInt::sample(bits);
+        candidate.set_bit(bits - 1, true);
+        candidate.set_bit(bits - 2, true);
+        let p = candidate.next_prime();
+        if p.bit_length() == bits && BigInt::modulus(&p, &four) == three {
+            return p;
+        }
+    }
+}
+
+/// Generates a Paillier key whose modulus is a Blum integer of `bits` bits.
+pub fn blum_paillier_keypair_with_modulus_size(bits: usize) -> (EncryptionKey, DecryptionKey) {
+    let p = sample_blum_prime(bits / 2);
+    let q = loop {
+        let q = sample_blum_prime(bits / 2);
+        if q != p {
+            break q;
+        }
+    };
+    Keypair { p, q }.keys()
+}
+
+pub fn blum_paillier_keypair() -> (EncryptionKey, DecryptionKey) {
+    blum_paillier_keypair_with_modulus_size(PAILLIER_MIN_BITS)
+}
+
+#[derive(Serialize, Deserialize)]
+struct PaillierKeyPair {
+    ek: EncryptionKey,
+    dk: DecryptionKey,
+    encrypted_share: BigInt,
+    randomness: BigInt,
+}
+
+/// Same as `party_one::PaillierKeyPair::generate_keypair_and_encrypted_share`,
+/// but the modulus is a Blum integer so that it admits a Paillier-Blum proof.
+/// The fork has no constructor for the result, which is built through serde;
+/// `tests::paillier_key_pair_round_trip` pins that layout.
+pub(crate) fn li17_p1_paillier_key_pair(
+    ec_key_pair: &party_one::EcKeyPair,
+) -> Result<(party_one::PaillierKeyPair, DecryptionKey), LindellError> {
+    let mut ec_key_pair = serde_json::to_value(ec_key_pair)?;
+    let secret_share: Scalar<Secp256k1> =
+        serde_json::from_value(ec_key_pair["secret_share"].take())?;
+    let (ek, dk) = blum_paillier_keypair();
+    let randomness = Randomness::sample(&ek);
+    let encrypted_share = Paillier::encrypt_with_chosen_randomness(
+        &ek,
+        RawPlaintext::from(secret_share.to_bigint()),
+        &randomness,
+    )
+    .0
+    .into_owned();
+    let paillier_key_pair = PaillierKeyPair {
+        ek,
+        dk: dk.clone(),
+        encrypted_share,
+        randomness: randomness.0,
+    };
+    Ok((
+        serde_json::from_value(serde_json::to_value(&paillier_key_pair)?)?,
+        dk,
+    ))
+}
+
+/// Ring-Pedersen parameters `(N, s = g, t = ni)` generated by the verifier of
+/// the no small factor proof, with a Π^prm proof that `s = t^λ`.
+pub fn ring_pedersen_setup() -> (DLogStatement, RingPedersenProof) {
+    let p = sample_blum_prime(PAILLIER_MIN_BITS / 2);
+    let q = loop {
+        let q = sample_blum_prime(PAILLIER_MIN_BITS / 2);
+        if q != p {
+            break q;
+        }
+    };
+    let n = &p * &q;
+    let phi = (&p - BigInt::one()) * (&q - BigInt::one());
+
+    // t is a random square, so it generates the quadratic residues
+    let t = loop {
+        let r = BigInt::sample_below(&n);
+        let t = BigInt::mod_pow(&r, &BigInt::from(2), &n);
+        if t > BigInt::one() && BigInt::gcd(&t, &n) == BigInt::one() {
+            break t;
+        }
+    };
+    let lambda = BigInt::sample_below(&phi);
+    let s = BigInt::mod_pow(&t, &lambda, &n);
+    let statement = DLogStatement { N: n, g: s, ni: t };
+    let proof = RingPedersenProof::prove(&statement, &lambda, &phi);
+    (statement, proof)
+}
+
+/// Checks the Ring-Pedersen parameters received from the other party: the
+/// modulus size, that `t` is a usable generator and the Π^prm proof.
+pub fn verify_ring_pedersen(
+    statement: &DLogStatement,
+    proof: &RingPedersenProof,
+) -> Result<(), LindellError> {
+    let bits = statement.N.bit_length();
+    if bits < PAILLIER_MIN_BITS {
+        return Err(LindellError::RingPedersenModulusTooSmall(bits));
+    }
+    if bits > PAILLIER_MAX_BITS {
+        return Err(LindellError::RingPedersenModulusTooLarge(bits));
+    }
+    proof.verify(statement)
+}
+
+pub fn check_modulus_size(ek: &EncryptionKey) -> Result<(), LindellError> {
+    let bits = ek.n.bit_length();
+    if bits < PAILLIER_MIN_BITS {
+        return Err(LindellError::PaillierModulusTooSmall(bits));
+    }
+    if bits > PAILLIER_MAX_BITS {
+        return Err(LindellError::PaillierModulusTooLarge(bits));
+    }
+    Ok(())
+}
+
+/// Checks everything about P1's Paillier modulus that `NiCorrectKeyProof`
+/// leaves open: its size, that it is a Blum integer and that it has no small factors.
+pub fn verify_paillier_modulus(
+    ek: &EncryptionKey,
+    blum_proof: &PaillierBlumModulusProof,
+    no_small_factor_proof: &NoSmallFactorProof,
+    ring_pedersen: &DLogStatement,
+) -> Result<(), LindellError> {
+    check_modulus_size(ek)?;
+    blum_proof.verify(ek)?;
+    no_small_factor_proof.verify(ek, ring_pedersen)
+}
+
+fn hash_bigints(tag: &[u8], items: &[&BigInt], counter: u32) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    for item in items {
+        let bytes = item.to_bytes();
+        hasher.update((bytes.len() as u64).to_be_bytes());
+        hasher.update(&bytes);
+    }
+    hasher.update(counter.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+fn mod_pow_signed(base: &BigInt, exponent: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+    if exponent.is_negative() {
+        let inv = BigInt::mod_inv(base, modulus)?;
+        Some(BigInt::mod_pow(
+            &inv,
+            &(BigInt::from(0) - exponent),
+            modulus,
+        ))
+    } else {
+        Some(BigInt::mod_pow(base, exponent, modulus))
+    }
+}
+
+fn sample_plus_minus(bound: &BigInt) -> BigInt {
+    BigInt::sample_below(&(bound * BigInt::from(2))) - bound
+}
+
+fn legendre(a: &BigInt, p: &BigInt) -> i8 {
+    let r = BigInt::mod_pow(a, &((p - BigInt::one()) >> 1), p);
+    if r.is_zero() {
+        0
+    } else if r == BigInt::one() {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Π^mod proof that `N = p * q` with `p = q = 3 mod 4` and `gcd(N, φ(N)) = 1`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierBlumModulusProof {
+    w: BigInt,
+    x: Vec<BigInt>,
+    a: Vec<bool>,
+    b: Vec<bool>,
+    z: Vec<BigInt>,
+}
+
+impl PaillierBlumModulusProof {
+    fn challenge(n: &BigInt, w: &BigInt, index: usize) -> BigInt {
+        // expand past the modulus size so the reduction is close to uniform
+        let blocks = (n.bit_length() + 128 + 255) / 256;
+        let mut bytes = vec![];
+        for j in 0..blocks {
+            bytes.extend(hash_bigints(
+                b"li17 paillier blum",
+                &[n, w, &BigInt::from(index as u64)],
+                j as u32,
+            ));
+        }
+        BigInt::modulus(&BigInt::from_bytes(&bytes), n)
+    }
+
+    fn adjust(y: &BigInt, w: &BigInt, a: bool, b: bool, n: &BigInt) -> BigInt {
+        let mut y = y.clone();
+        if b {
+            y = BigInt::mod_mul(&y, w, n);
+        }
+        if a {
+            y = BigInt::mod_sub(n, &y, n);
+        }
+        y
+    }
+
+    pub fn prove(ek: &EncryptionKey, dk: &DecryptionKey) -> Result<Self, LindellError> {
+        let (n, p, q) = (&ek.n, &dk.p, &dk.q);
+        let err = || LindellError::InvalidPaillierBlumProof;
+        let one = BigInt::one();
+        let four = BigInt::from(4);
+        if BigInt::modulus(p, &four) != BigInt::from(3)
+            || BigInt::modulus(q, &four) != BigInt::from(3)
+        {
+            return Err(err());
+        }
+        let phi = (p - &one) * (q - &one);
+        let n_inv = BigInt::mod_inv(n, &phi).ok_or_else(err)?;
+
+        // w has Jacobi symbol -1, so it is a residue modulo exactly one prime
+        let w = loop {
+            let w = BigInt::sample_below(n);
+            if legendre(&w, p) * legendre(&w, q) == -1 {
+                break w;
+            }
+        };
+
+        // for p = 3 mod 4 the fourth root of a residue is y^(((p + 1) / 4)^2)
+        let exp_p = BigInt::mod_pow(&((p + &one) >> 2), &BigInt::from(2), &(p - &one));
+        let exp_q = BigInt::mod_pow(&((q + &one) >> 2), &BigInt::from(2), &(q - &one));
+        let p_inv = BigInt::mod_inv(p, q).ok_or_else(err)?;
+
+        let mut proof = PaillierBlumModulusProof {
+            w,
+            x: vec![],
+            a: vec![],
+            b: vec![],
+            z: vec![],
+        };
+        for i in 0..BLUM_ITERATIONS {
+            let y = Self::challenge(n, &proof.w, i);
+            let (a, b, y_adj) = [(false, false), (true, false), (false, true), (true, true)]
+                .iter()
+                .map(|&(a, b)| (a, b, Self::adjust(&y, &proof.w, a, b, n)))
+                .find(|(_, _, y_adj)| legendre(y_adj, p) == 1 && legendre(y_adj, q) == 1)
+                .ok_or_else(err)?;
+
+            let root_p = BigInt::mod_pow(&y_adj, &exp_p, p);
+            let root_q = BigInt::mod_pow(&y_adj, &exp_q, q);
+            let h = BigInt::mod_mul(&BigInt::mod_sub(&root_q, &root_p, q), &p_inv, q);
+            let x = root_p + p * h;
+
+            proof.x.push(x);
+            proof.a.push(a);
+            proof.b.push(b);
+            proof.z.push(BigInt::mod_pow(&y, &n_inv, n));
+        }
+        Ok(proof)
+    }
+
+    pub fn verify(&self, ek: &EncryptionKey) -> Result<(), LindellError> {
+        let n = &ek.n;
+        let err = || LindellError::InvalidPaillierBlumProof;
+        if n.is_even() || n.is_probable_prime(30) {
+            return Err(err());
+        }
+        if self.x.len() != BLUM_ITERATIONS
+            || self.a.len() != BLUM_ITERATIONS
+            || self.b.len() != BLUM_ITERATIONS
+            || self.z.len() != BLUM_ITERATIONS
+        {
+            return Err(err());
+        }
+        if self.w.is_zero() || self.w.is_negative() || &self.w >= n {
+            return Err(err());
+        }
+
+        for i in 0..BLUM_ITERATIONS {
+            let y = Self::challenge(n, &self.w, i);
+            if BigInt::mod_pow(&self.z[i], n, n) != y {
+                return Err(err());
+            }
+            let y_adj = Self::adjust(&y, &self.w, self.a[i], self.b[i], n);
+            if BigInt::mod_pow(&self.x[i], &BigInt::from(4), n) != y_adj {
+                return Err(err());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Π^prm proof that `s = t^λ mod N` for the Ring-Pedersen parameters
+/// `(N, s, t)`, with binary challenges.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RingPedersenProof {
+    a: Vec<BigInt>,
+    z: Vec<BigInt>,
+}
+
+impl RingPedersenProof {
+    fn challenge(rp: &DLogStatement, a: &[BigInt]) -> Vec<bool> {
+        let mut items = vec![&rp.N, &rp.g, &rp.ni];
+        items.extend(a);
+        let hash = hash_bigints(b"li17 ring pedersen", &items, 0);
+        (0..RING_PEDERSEN_ITERATIONS)
+            .map(|i| (hash[i / 8] >> (i % 8)) & 1 == 1)
+            .collect()
+    }
+
+    fn prove(rp: &DLogStatement, lambda: &BigInt, phi: &BigInt) -> Self {
+        let alpha: Vec<BigInt> = (0..RING_PEDERSEN_ITERATIONS)
+            .map(|_| BigInt::sample_below(phi))
+            .collect();
+        let a: Vec<BigInt> = alpha
+            .iter()
+            .map(|alpha| BigInt::mod_pow(&rp.ni, alpha, &rp.N))
+            .collect();
+        let e = Self::challenge(rp, &a);
+        let z = alpha
+            .iter()
+            .zip(e)
+            .map(|(alpha, e)| {
+                if e {
+                    BigInt::mod_add(alpha, lambda, phi)
+                } else {
+                    alpha.clone()
+                }
+            })
+            .collect();
+        RingPedersenProof { a, z }
+    }
+
+    pub fn verify(&self, rp: &DLogStatement) -> Result<(), LindellError> {
+        let n = &rp.N;
+        let err = || LindellError::InvalidRingPedersenProof;
+        if n.is_even() || n.is_probable_prime(30) {
+            return Err(err());
+        }
+        for x in [&rp.g, &rp.ni] {
+            if x.is_negative() || x >= n || BigInt::gcd(x, n) != BigInt::one() {
+                return Err(err());
+            }
+        }
+        // t = 0 is covered by the gcd check, t = 1 or t = s makes λ trivial
+        if rp.ni == BigInt::one() || rp.ni == rp.g {
+            return Err(err());
+        }
+        if self.a.len() != RING_PEDERSEN_ITERATIONS || self.z.len() != RING_PEDERSEN_ITERATIONS {
+            return Err(err());
+        }
+
+        let e = Self::challenge(rp, &self.a);
+        for ((a, z), e) in self.a.iter().zip(&self.z).zip(e) {
+            if a.is_zero() || a.is_negative() || a >= n || z.is_negative() {
+                return Err(err());
+            }
+            let rhs = if e {
+                BigInt::mod_mul(a, &rp.g, n)
+            } else {
+                a.clone()
+            };
+            if BigInt::mod_pow(&rp.ni, z, n) != rhs {
+                return Err(err());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Π^fac proof that both prime factors of `N` are larger than about
+/// `2^-ℓ * sqrt(N)`, against the verifier's Ring-Pedersen parameters.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoSmallFactorProof {
+    p_commitment: BigInt,
+    q_commitment: BigInt,
+    a: BigInt,
+    b: BigInt,
+    t: BigInt,
+    sigma: BigInt,
+    z1: BigInt,
+    z2: BigInt,
+    w1: BigInt,
+    w2: BigInt,
+    v: BigInt,
+}
+
+fn sqrt_bound(n: &BigInt) -> BigInt {
+    BigInt::one() << ((n.bit_length() + 1) / 2)
+}
+
+fn pedersen(
+    rp: &DLogStatement,
+    base: &BigInt,
+    m: &BigInt,
+    r: &BigInt,
+) -> Result<BigInt, LindellError> {
+    let err = || LindellError::InvalidNoSmallFactorProof;
+    let bm = mod_pow_signed(base, m, &rp.N).ok_or_else(err)?;
+    let tr = mod_pow_signed(&rp.ni, r, &rp.N).ok_or_else(err)?;
+    Ok(BigInt::mod_mul(&bm, &tr, &rp.N))
+}
+
+impl NoSmallFactorProof {
+    fn challenge(&self, n: &BigInt, rp: &DLogStatement) -> BigInt {
+        BigInt::from_bytes(&hash_bigints(
+            b"li17 no small factor",
+            &[
+                n,
+                &rp.N,
+                &rp.g,
+                &rp.ni,
+                &self.p_commitment,
+                &self.q_commitment,
+                &self.a,
+                &self.b,
+                &self.t,
+                &self.sigma,
+            ],
+            0,
+        ))
+    }
+
+    pub fn prove(
+        ek: &EncryptionKey,
+        dk: &DecryptionKey,
+        ring_pedersen: &DLogStatement,
+    ) -> Result<Self, LindellError> {
+        let rp = ring_pedersen;
+        let (n0, p, q) = (&ek.n, &dk.p, &dk.q);
+        let sqrt_n0 = sqrt_bound(n0);
+
+        let alpha = sample_plus_minus(&(&sqrt_n0 << (FAC_L + FAC_EPSILON)));
+        let beta = sample_plus_minus(&(&sqrt_n0 << (FAC_L + FAC_EPSILON)));
+        let mu = sample_plus_minus(&(&rp.N << FAC_L));
+        let nu = sample_plus_minus(&(&rp.N << FAC_L));
+        let sigma = sample_plus_minus(&((n0 * &rp.N) << FAC_L));
+        let r = sample_plus_minus(&((n0 * &rp.N) << (FAC_L + FAC_EPSILON)));
+        let x = sample_plus_minus(&(&rp.N << (FAC_L + FAC_EPSILON)));
+        let y = sample_plus_minus(&(&rp.N << (FAC_L + FAC_EPSILON)));
+
+        let p_commitment = pedersen(rp, &rp.g, p, &mu)?;
+        let q_commitment = pedersen(rp, &rp.g, q, &nu)?;
+        let a = pedersen(rp, &rp.g, &alpha, &x)?;
+        let b = pedersen(rp, &rp.g, &beta, &y)?;
+        let t = pedersen(rp, &q_commitment, &alpha, &r)?;
+
+        let mut proof = NoSmallFactorProof {
+            p_commitment,
+            q_commitment,
+            a,
+            b,
+            t,
+            sigma,
+            z1: BigInt::from(0),
+            z2: BigInt::from(0),
+            w1: BigInt::from(0),
+            w2: BigInt::from(0),
+            v: BigInt::from(0),
+        };
+        let e = proof.challenge(n0, rp);
+
+        let sigma_hat = &proof.sigma - &nu * p;
+        proof.z1 = alpha + &e * p;
+        proof.z2 = beta + &e * q;
+        proof.w1 = x + &e * &mu;
+        proof.w2 = y + &e * &nu;
+        proof.v = r + &e * &sigma_hat;
+        Ok(proof)
+    }
+
+    pub fn verify(
+        &self,
+        ek: &EncryptionKey,
+        ring_pedersen: &DLogStatement,
+    ) -> Result<(), LindellError> {
+        let rp = ring_pedersen;
+        let n0 = &ek.n;
+        let err = || LindellError::InvalidNoSmallFactorProof;
+
+        for c in [
+            &self.p_commitment,
+            &self.q_commitment,
+            &self.a,
+            &self.b,
+            &self.t,
+        ] {
+            if c.is_zero() || c.is_negative() || c >= &rp.N {
+                return Err(err());
+            }
+        }
+
+        let bound = sqrt_bound(n0) << (FAC_L + FAC_EPSILON);
+        let neg_bound = BigInt::from(0) - &bound;
+        if self.z1 > bound || self.z1 < neg_bound || self.z2 > bound || self.z2 < neg_bound {
+            return Err(err());
+        }
+
+        let e = self.challenge(n0, rp);
+        let r = pedersen(rp, &rp.g, n0, &self.sigma)?;
+
+        let lhs = pedersen(rp, &rp.g, &self.z1, &self.w1)?;
+        let rhs = BigInt::mod_mul(
+            &self.a,
+            &BigInt::mod_pow(&self.p_commitment, &e, &rp.N),
+            &rp.N,
+        );
+        if lhs != rhs {
+            return Err(err());
+        }
+
+        let lhs = pedersen(rp, &rp.g, &self.z2, &self.w2)?;
+        let rhs = BigInt::mod_mul(
+            &self.b,
+            &BigInt::mod_pow(&self.q_commitment, &e, &rp.N),
+            &rp.N,
+        );
+        if lhs != rhs {
+            return Err(err());
+        }
+
+        let lhs = pedersen(rp, &self.q_commitment, &self.z1, &self.v)?;
+        let rhs = BigInt::mod_mul(&self.t, &BigInt::mod_pow(&r, &e, &rp.N), &rp.N);
+        if lhs != rhs {
+            return Err(err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Point, Secp256k1};
+use curv::BigInt;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::derive::li17_p2_derive_child;
+use crate::error::LindellError;
+use crate::keygen::{Li17SignP1Context, Li17SignP2Context};
+use crate::sign::{root_public, verify_or_taint};
+
+// Presignatures run the message-independent ephemeral key exchange ahead of
+// time. Each one holds a nonce share and must never sign more than one hash,
+// so the presignature types are not `Clone` and are consumed by value. P1's
+// root signing context numbers presignatures as they are made, and both root
+// contexts only sign with a sequence number above the last one used: a
+// presignature that was copied through serde is still refused, whichever
+// derived key it signs for, and the contexts stay the same size however many
+// presignatures they use. Presignatures must therefore be used in order; an
+// older one left behind is dropped. The updated context must be persisted
+// before the signing message is sent.
+
+pub trait Li17Presignature {
+    fn id(&self) -> &str;
+    fn seq(&self) -> u64;
+}
+
+// party two structures
+#[derive(Serialize, Deserialize)]
+pub struct Li17PresignP2Context1 {
+    p2_msg1: party_two::EphKeyGenFirstMsg,
+    p2_eph_comm_witness: party_two::EphCommWitness,
+    p2_eph_ec_key_pair: party_two::EphEcKeyPair,
+}
+
+pub type Li17PresignP2Msg1 = party_two::EphKeyGenFirstMsg;
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17P2Presignature {
+    id: String,
+    seq: u64,
+    p2_eph_ec_key_pair: party_two::EphEcKeyPair,
+    p2_eph_second_msg: party_two::EphKeyGenSecondMsg,
+    p1_eph_public: Point<Secp256k1>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17PresignP2Msg2 {
+    pub presignature_id: String,
+    pub partial_sig: party_two::PartialSig,
+    pub eph_second_msg: party_two::EphKeyGenSecondMsg,
+}
+
+// party one structures
+#[derive(Serialize, Deserialize)]
+pub struct Li17P1Presignature {
+    id: String,
+    seq: u64,
+    p1_eph_ec_key_pair: party_one::EphEcKeyPair,
+    p1_msg1_from_p2: Li17PresignP2Msg1,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17PresignP1Msg1 {
+    pub seq: u64,
+    pub eph_first_msg: party_one::EphKeyGenFirstMsg,
+}
+
+impl Li17Presignature for Li17P1Presignature {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+impl Li17Presignature for Li17P2Presignature {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn seq(&self) -> u64 {
+        self.seq
+    }
+}
+
+fn presignature_id(
+    seq: u64,
+    p2_msg1: &Li17PresignP2Msg1,
+    p1_eph_public: &Point<Secp256k1>,
+) -> Result<String, LindellError> {
+    let mut hasher = Sha256::new();
+    hasher.update(seq.to_be_bytes());
+    hasher.update(serde_json::to_vec(p2_msg1)?);
+    hasher.update(&*p1_eph_public.to_bytes(true));
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Moves the high-water mark to `seq`, or fails if a presignature at or
+/// after it has already been used.
+fn use_presignature(high_water: &mut u64, seq: u64, id: &str) -> Result<(), LindellError> {
+    if seq <= *high_water {
+        return Err(LindellError::PresignatureUsed(id.to_string()));
+    }
+    *high_water = seq;
+    Ok(())
+}
+
+/// Keeps unused presignatures in order and the sequence number of the last
+/// one handed out, so a presignature cannot be inserted or taken a second
+/// time.
+#[derive(Serialize, Deserialize)]
+pub struct Li17PresignatureStore<P> {
+    presignatures: BTreeMap<u64, P>,
+    high_water: u64,
+}
+
+impl<P: Li17Presignature> Li17PresignatureStore<P> {
+    pub fn new() -> Self {
+        Li17PresignatureStore {
+            presignatures: BTreeMap::new(),
+            high_water: 0,
+        }
+    }
+
+    pub fn insert(&mut self, presignature: P) -> Result<(), LindellError> {
+        let seq = presignature.seq();
+        if seq <= self.high_water || self.presignatures.contains_key(&seq) {
+            return Err(LindellError::PresignatureUsed(
+                presignature.id().to_string(),
+            ));
+        }
+        self.presignatures.insert(seq, presignature);
+        Ok(())
+    }
+
+    /// Takes the presignature `id`, dropping the older ones that can no
+    /// longer be used.
+    pub fn take(&mut self, id: &str) -> Result<P, LindellError> {
+        let seq = self
+            .presignatures
+            .iter()
+            .find(|(_, presignature)| presignature.id() == id)
+            .map(|(&seq, _)| seq)
+            .ok_or_else(|| LindellError::PresignatureNotFound(id.to_string()))?;
+        use_presignature(&mut self.high_water, seq, id)?;
+        let mut newer = self.presignatures.split_off(&seq);
+        let presignature = newer
+            .remove(&seq)
+            .ok_or_else(|| LindellError::PresignatureNotFound(id.to_string()))?;
+        self.presignatures = newer;
+        Ok(presignature)
+    }
+
+    /// Ids of the unused presignatures, oldest first.
+    pub fn ids(&self) -> Vec<String> {
+        self.presignatures
+            .values()
+            .map(|presignature| presignature.id().to_string())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.presignatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presignatures.is_empty()
+    }
+}
+
+impl<P: Li17Presignature> Default for Li17PresignatureStore<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// party two functions
+pub fn li17_p2_presign1() -> Result<(Li17PresignP2Context1, Li17PresignP2Msg1), LindellError> {
+    let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
+        party_two::EphKeyGenFirstMsg::create_commitments();
+
+    let context1 = Li17PresignP2Context1 {
+        p2_msg1: eph_party_two_first_message.clone(),
+        p2_eph_comm_witness: eph_comm_witness,
+        p2_eph_ec_key_pair: eph_ec_key_pair_party2,
+    };
+    Ok((context1, eph_party_two_first_message))
+}
+
+pub fn li17_p2_presign2(
+    context: Li17PresignP2Context1,
+    msg: Li17PresignP1Msg1,
+) -> Result<Li17P2Presignature, LindellError> {
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        context.p2_eph_comm_witness,
+        &msg.eph_first_msg,
+    )?;
+
+    Ok(Li17P2Presignature {
+        id: presignature_id(msg.seq, &context.p2_msg1, &msg.eph_first_msg.public_share)?,
+        seq: msg.seq,
+        p2_eph_ec_key_pair: context.p2_eph_ec_key_pair,
+        p2_eph_second_msg: eph_party_two_second_message,
+        p1_eph_public: msg.eph_first_msg.public_share,
+    })
+}
+
+/// Online phase for P2: the only message sent once the hash is known. Signs
+/// for the key derived from `root_context` along `path`.
+pub fn li17_p2_presign_sign(
+    root_context: &mut Li17SignP2Context,
+    path: &[u32],
+    presignature: Li17P2Presignature,
+    message_hash: &Vec<u8>,
+) -> Result<Li17PresignP2Msg2, LindellError> {
+    if !root_context.path.is_empty() {
+        return Err(LindellError::SpecificError(
+            "presignatures must be used with the root context".to_string(),
+        ));
+    }
+    use_presignature(
+        &mut root_context.presignature_high_water,
+        presignature.seq,
+        &presignature.id,
+    )?;
+    let child_context;
+    let context = if path.is_empty() {
+        &*root_context
+    } else {
+        child_context = li17_p2_derive_child(root_context, path)?;
+        &child_context
+    };
+    let partial_sig = party_two::PartialSig::compute(
+        &context.p2_paillier_public.ek,
+        &context.p2_paillier_public.encrypted_secret_share,
+        &context.p2_private,
+        &presignature.p2_eph_ec_key_pair,
+        &presignature.p1_eph_public,
+        &BigInt::from_bytes(message_hash),
+    );
+    Ok(Li17PresignP2Msg2 {
+        presignature_id: presignature.id,
+        partial_sig,
+        eph_second_msg: presignature.p2_eph_second_msg,
+    })
+}
+
+// party one functions
+/// Makes the next presignature of `root_context`, which must be persisted
+/// before the message is sent.
+pub fn li17_p1_presign1(
+    root_context: &mut Li17SignP1Context,
+    msg: Li17PresignP2Msg1,
+) -> Result<(Li17P1Presignature, Li17PresignP1Msg1), LindellError> {
+    if !root_context.path.is_empty() {
+        return Err(LindellError::SpecificError(
+            "presignatures must be made with the root context".to_string(),
+        ));
+    }
+    let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+        party_one::EphKeyGenFirstMsg::create();
+    root_context.presignatures_issued += 1;
+    let seq = root_context.presignatures_issued;
+
+    let presignature = Li17P1Presignature {
+        id: presignature_id(seq, &msg, &eph_party_one_first_message.public_share)?,
+        seq,
+        p1_eph_ec_key_pair: eph_ec_key_pair_party1,
+        p1_msg1_from_p2: msg,
+    };
+    let msg1 = Li17PresignP1Msg1 {
+        seq,
+        eph_first_msg: eph_party_one_first_message,
+    };
+    Ok((presignature, msg1))
+}
+
+/// Online phase for P1: finishes the signature from P2's single message, for
+/// the key derived from `root_context` along `path`.
+pub fn li17_p1_presign_sign(
+    root_context: &mut Li17SignP1Context,
+    path: &[u32],
+    presignature: Li17P1Presignature,
+    msg: Li17PresignP2Msg2,
+    message_hash: &Vec<u8>,
+) -> Result<SignatureRecid, LindellError> {
+    let public = root_public(root_context, path)?;
+    use_presignature(
+        &mut root_context.presignature_high_water,
+        presignature.seq,
+        &presignature.id,
+    )?;
+    if msg.presignature_id != presignature.id {
+        return Err(LindellError::PresignatureMismatch);
+    }
+
+    party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+        &presignature.p1_msg1_from_p2,
+        &msg.eph_second_msg,
+    )?;
+
+    let sig = party_one::Signature::compute_with_recid(
+        &root_context.p1_private,
+        &msg.partial_sig.c3,
+        &presignature.p1_eph_ec_key_pair,
+        &msg.eph_second_msg.comm_witness.public_share,
+    );
+
+    verify_or_taint(
+        root_context,
+        &public,
+        &sig,
+        &BigInt::from_bytes(message_hash),
+    )?;
+
+    Ok(sig)
+}
@@ -0,0 +1,280 @@
+use curv::{
+    arithmetic::traits::*,
+    cryptographic_primitives::commitments::{hash_commitment::HashCommitment, traits::Commitment},
+    elliptic::curves::{Point, Scalar, Secp256k1},
+    BigInt,
+};
+use multi_party_ecdsa::{
+    protocols::two_party_ecdsa::lindell_2017::{party_one, party_two},
+    utilities::zk_pdl_with_slack::{PDLwSlackProof, PDLwSlackStatement},
+};
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NiCorrectKeyProof};
+
+use crate::{
+    error::LindellError,
+    keygen::{Li17SignP1Context, Li17SignP2Context},
+    li17_p1_exract_secret, li17_p2_exract_secret,
+    modulus::{
+        li17_p1_paillier_key_pair, ring_pedersen_setup, verify_paillier_modulus,
+        verify_ring_pedersen, NoSmallFactorProof, PaillierBlumModulusProof, RingPedersenProof,
+    },
+    sign::check_not_tainted,
+};
+
+// Both shares are rotated multiplicatively, x1' = x1 * r and x2' = x2 * r^-1,
+// so the joint public key x1 * x2 * G is left untouched. The joint secret is
+// unchanged too, so the presignature counters carry over. P2 knows r, so a
+// tainted share stays tainted after a refresh and P1 refuses to refresh it.
+//
+// r is hashed from a Diffie-Hellman secret between the two seeds. A relay
+// that saw an old share and carries the refresh transcript would learn the
+// new share from r; with only `seed * G` on the wire it cannot compute r.
+
+// party one structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17RefreshP1Context1 {
+    sign_context: Li17SignP1Context,
+    seed: Scalar<Secp256k1>,
+    blind_factor: BigInt,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17RefreshP1Msg1 {
+    pub seed_commitment: BigInt,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17RefreshP1Msg2 {
+    pub seed_point: Point<Secp256k1>,
+    pub blind_factor: BigInt,
+    pub correct_key_proof: NiCorrectKeyProof,
+    pub pdl_statement: PDLwSlackStatement,
+    pub pdl_proof: PDLwSlackProof,
+    pub composite_dlog_proof: CompositeDLogProof,
+    pub ek: EncryptionKey,
+    pub encrypted_share: BigInt,
+    pub blum_proof: PaillierBlumModulusProof,
+    pub no_small_factor_proof: NoSmallFactorProof,
+}
+
+//party two structures
+#[derive(Serialize, Deserialize)]
+pub struct Li17RefreshP2Context1 {
+    sign_context: Li17SignP2Context,
+    seed: Scalar<Secp256k1>,
+    p2_msg1_from_p1: Li17RefreshP1Msg1,
+    ring_pedersen: DLogStatement,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17RefreshP2Msg1 {
+    pub seed_point: Point<Secp256k1>,
+    pub ring_pedersen: DLogStatement,
+    pub ring_pedersen_proof: RingPedersenProof,
+}
+
+fn seed_commitment(seed_point: &Point<Secp256k1>, blind_factor: &BigInt) -> BigInt {
+    HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+        &BigInt::from_bytes(&seed_point.to_bytes(true)),
+        blind_factor,
+    )
+}
+
+fn refresh_factor(
+    seed: &Scalar<Secp256k1>,
+    peer_seed_point: &Point<Secp256k1>,
+) -> Result<Scalar<Secp256k1>, LindellError> {
+    if peer_seed_point.is_zero() {
+        return Err(LindellError::SpecificError(
+            "refresh seed is the identity".to_string(),
+        ));
+    }
+    let shared = peer_seed_point * seed;
+    let hash = Sha256::new()
+        .chain(b"lindell17 refresh factor")
+        .chain(&*shared.to_bytes(true))
+        .finalize();
+    let factor = Scalar::<Secp256k1>::from_bigint(&BigInt::from_bytes(&hash));
+    if factor.is_zero() {
+        return Err(LindellError::SpecificError(
+            "refresh factor is zero".to_string(),
+        ));
+    }
+    Ok(factor)
+}
+
+// A derived context shares P1's secrets with its root but not the public
+// shares, so only the root can be refreshed.
+fn check_root(path: &[u32]) -> Result<(), LindellError> {
+    if !path.is_empty() {
+        return Err(LindellError::DeriveError(
+            "derived keys cannot be refreshed, refresh the root key and derive again".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// party one functions
+pub fn li17_p1_refresh1(
+    context: Li17SignP1Context,
+) -> Result<(Li17RefreshP1Context1, Li17RefreshP1Msg1), LindellError> {
+    check_root(&context.path)?;
+    check_not_tainted(&context)?;
+    let seed = Scalar::<Secp256k1>::random();
+    let blind_factor = BigInt::sample(256);
+    let seed_commitment = seed_commitment(&(Point::generator() * &seed), &blind_factor);
+
+    let context1 = Li17RefreshP1Context1 {
+        sign_context: context,
+        seed,
+        blind_factor,
+    };
+    Ok((context1, Li17RefreshP1Msg1 { seed_commitment }))
+}
+
+pub fn li17_p1_refresh2(
+    context: Li17RefreshP1Context1,
+    msg: Li17RefreshP2Msg1,
+) -> Result<(Li17SignP1Context, Li17RefreshP1Msg2), LindellError> {
+    verify_ring_pedersen(&msg.ring_pedersen, &msg.ring_pedersen_proof)?;
+    let factor = refresh_factor(&context.seed, &msg.seed_point)?;
+    let x1 = li17_p1_exract_secret(&context.sign_context)?;
+    let x1_new = x1 * &factor;
+
+    let (_, _, p1_ec_key_pair) =
+        party_one::KeyGenFirstMsg::create_commitments_with_fixed_secret_share(x1_new);
+
+    let (paillier_key_pair, paillier_dk) = li17_p1_paillier_key_pair(&p1_ec_key_pair)?;
+    let party_one_private =
+        party_one::Party1Private::set_private_key(&p1_ec_key_pair, &paillier_key_pair);
+
+    let correct_key_proof =
+        party_one::PaillierKeyPair::generate_ni_proof_correct_key(&paillier_key_pair);
+
+    let (pdl_statement, pdl_proof, composite_dlog_proof) =
+        party_one::PaillierKeyPair::pdl_proof(&party_one_private, &paillier_key_pair);
+    let blum_proof = PaillierBlumModulusProof::prove(&paillier_key_pair.ek, &paillier_dk)?;
+    let no_small_factor_proof =
+        NoSmallFactorProof::prove(&paillier_key_pair.ek, &paillier_dk, &msg.ring_pedersen)?;
+
+    let factor_inv = factor
+        .invert()
+        .ok_or_else(|| LindellError::SpecificError("refresh factor is zero".to_string()))?;
+    let sign_context = Li17SignP1Context {
+        public: context.sign_context.public,
+        public_p1: p1_ec_key_pair.public_share.clone(),
+        public_p2: context.sign_context.public_p2 * &factor_inv,
+        p1_private: party_one_private,
+        chain_code: context.sign_context.chain_code,
+        path: vec![],
+        presignatures_issued: context.sign_context.presignatures_issued,
+        presignature_high_water: context.sign_context.presignature_high_water,
+        tainted: context.sign_context.tainted,
+    };
+
+    let msg2 = Li17RefreshP1Msg2 {
+        seed_point: Point::generator() * &context.seed,
+        blind_factor: context.blind_factor,
+        correct_key_proof,
+        pdl_statement,
+        pdl_proof,
+        composite_dlog_proof,
+        ek: paillier_key_pair.ek.clone(),
+        encrypted_share: paillier_key_pair.encrypted_share.clone(),
+        blum_proof,
+        no_small_factor_proof,
+    };
+    Ok((sign_context, msg2))
+}
+
+// party two functions
+pub fn li17_p2_refresh1(
+    context: Li17SignP2Context,
+    msg: Li17RefreshP1Msg1,
+) -> Result<(Li17RefreshP2Context1, Li17RefreshP2Msg1), LindellError> {
+    check_root(&context.path)?;
+    let seed = Scalar::<Secp256k1>::random();
+    let (ring_pedersen, ring_pedersen_proof) = ring_pedersen_setup();
+    let msg1 = Li17RefreshP2Msg1 {
+        seed_point: Point::generator() * &seed,
+        ring_pedersen: ring_pedersen.clone(),
+        ring_pedersen_proof,
+    };
+    let context1 = Li17RefreshP2Context1 {
+        sign_context: context,
+        seed,
+        p2_msg1_from_p1: msg,
+        ring_pedersen,
+    };
+    Ok((context1, msg1))
+}
+
+pub fn li17_p2_refresh2(
+    context: Li17RefreshP2Context1,
+    msg: Li17RefreshP1Msg2,
+) -> Result<Li17SignP2Context, LindellError> {
+    if seed_commitment(&msg.seed_point, &msg.blind_factor)
+        != context.p2_msg1_from_p1.seed_commitment
+    {
+        return Err(LindellError::SpecificError(
+            "refresh seed commitment mismatch".to_string(),
+        ));
+    }
+
+    let factor = refresh_factor(&context.seed, &msg.seed_point)?;
+    let factor_inv = factor
+        .invert()
+        .ok_or_else(|| LindellError::SpecificError("refresh factor is zero".to_string()))?;
+    let public_p1: Point<Secp256k1> = &context.sign_context.public_p1 * &factor;
+
+    let party_two_paillier = party_two::PaillierPublic {
+        ek: msg.ek.clone(),
+        encrypted_secret_share: msg.encrypted_share.clone(),
+    };
+
+    party_two::PaillierPublic::verify_ni_proof_correct_key(
+        msg.correct_key_proof,
+        &party_two_paillier.ek,
+    )
+    .map_err(|_| LindellError::InvalidPaillierKeyProof)?;
+
+    verify_paillier_modulus(
+        &party_two_paillier.ek,
+        &msg.blum_proof,
+        &msg.no_small_factor_proof,
+        &context.ring_pedersen,
+    )?;
+
+    party_two::PaillierPublic::pdl_verify(
+        &msg.composite_dlog_proof,
+        &msg.pdl_statement,
+        &msg.pdl_proof,
+        &party_two_paillier,
+        &public_p1,
+    )
+    .map_err(|_| LindellError::InvalidPdlProof)?;
+
+    let x2 = li17_p2_exract_secret(&context.sign_context)?;
+    let (_, p2_ec_key_pair) =
+        party_two::KeyGenFirstMsg::create_with_fixed_secret_share(x2 * &factor_inv);
+    let public_key = party_two::compute_pubkey(&p2_ec_key_pair, &public_p1);
+    if public_key != context.sign_context.public {
+        return Err(LindellError::SpecificError(
+            "refreshed public key mismatch".to_string(),
+        ));
+    }
+
+    Ok(Li17SignP2Context {
+        public: public_key,
+        public_p1,
+        public_p2: p2_ec_key_pair.public_share.clone(),
+        p2_private: party_two::Party2Private::set_private_key(&p2_ec_key_pair),
+        p2_paillier_public: party_two_paillier,
+        chain_code: context.sign_context.chain_code,
+        path: vec![],
+        presignature_high_water: context.sign_context.presignature_high_water,
+    })
+}
@@ -4,39 +4,145 @@ use curv::BigInt;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::derive::derive_child_public;
 use crate::error::LindellError;
 
 use super::keygen::{Li17SignP1Context, Li17SignP2Context};
 
+// P1 starts a signature from any context, root or derived, but finishes it
+// with the root context: every derived key shares the root's `x1` and
+// Paillier key, so an abort taints the root and stops all of them.
+
 #[derive(Serialize, Deserialize)]
 pub struct Li17SignP2Context1 {
     pub public: Point<Secp256k1>,
     pub p2_private: party_two::Party2Private,
     pub p2_paillier_public: party_two::PaillierPublic,
+    session_id: Vec<u8>,
     hash: BigInt,
     p2_eph_comm_witness: party_two::EphCommWitness,
     p2_eph_ec_key_pair: party_two::EphEcKeyPair,
 }
 
-pub type Li17SignP2Msg1 = party_two::EphKeyGenFirstMsg;
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17SignP2Msg1 {
+    pub session_id: Vec<u8>,
+    pub hash_commitment: Vec<u8>,
+    pub eph_msg: party_two::EphKeyGenFirstMsg,
+}
 
-pub type Li17SignP2Msg2 = (party_two::PartialSig, party_two::EphKeyGenSecondMsg);
+#[derive(Serialize, Deserialize)]
+pub struct Li17SignP2Msg2 {
+    pub session_id: Vec<u8>,
+    pub partial_sig: party_two::PartialSig,
+    pub eph_msg: party_two::EphKeyGenSecondMsg,
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Li17SignP1Context1 {
     pub public: Point<Secp256k1>,
-    pub p1_private: party_one::Party1Private,
+    pub path: Vec<u32>,
+    session_id: Vec<u8>,
     hash: BigInt,
     p1_eph_ec_key_pair: party_one::EphEcKeyPair,
-    p1_msg1_from_p2: Li17SignP2Msg1,
+    p1_msg1_from_p2: party_two::EphKeyGenFirstMsg,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17SignP1Msg1 {
+    pub session_id: Vec<u8>,
+    pub eph_msg: party_one::EphKeyGenFirstMsg,
+}
+
+/// Binds the message hash to the session so P1 can check, before doing any
+/// work, that both parties are signing the same hash in the same session.
+pub fn hash_commitment(session_id: &[u8], message_hash: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"li17 sign");
+    hasher.update((session_id.len() as u64).to_be_bytes());
+    hasher.update(session_id);
+    hasher.update(message_hash);
+    hasher.finalize().to_vec()
+}
+
+fn check_session_id(expected: &[u8], received: &[u8]) -> Result<(), LindellError> {
+    if expected != received {
+        return Err(LindellError::SessionIdMismatch);
+    }
+    Ok(())
+}
+
+pub(crate) fn check_not_tainted(context: &Li17SignP1Context) -> Result<(), LindellError> {
+    if context.tainted {
+        return Err(LindellError::KeyTainted);
+    }
+    Ok(())
 }
 
-pub type Li17SignP1Msg1 = party_one::EphKeyGenFirstMsg;
+/// Marks the root context as tainted when a fully computed signature for
+/// `public` does not verify.
+pub(crate) fn verify_or_taint(
+    root_context: &mut Li17SignP1Context,
+    public: &Point<Secp256k1>,
+    sig: &SignatureRecid,
+    message_hash: &BigInt,
+) -> Result<(), LindellError> {
+    if verify(sig, public, message_hash).is_err() {
+        root_context.tainted = true;
+        return Err(LindellError::SignatureAbort);
+    }
+    Ok(())
+}
+
+/// Checks that `root_context` is an untainted root and returns the public key
+/// derived from it along `path`.
+pub(crate) fn root_public(
+    root_context: &Li17SignP1Context,
+    path: &[u32],
+) -> Result<Point<Secp256k1>, LindellError> {
+    check_not_tainted(root_context)?;
+    if !root_context.path.is_empty() {
+        return Err(LindellError::SpecificError(
+            "signatures must be finished with the root context".to_string(),
+        ));
+    }
+    if path.is_empty() {
+        return Ok(root_context.public.clone());
+    }
+    Ok(derive_child_public(&root_context.public, &root_context.chain_code, path)?.0)
+}
+
+fn check_root_context(
+    root_context: &Li17SignP1Context,
+    public: &Point<Secp256k1>,
+    path: &[u32],
+) -> Result<(), LindellError> {
+    if &root_public(root_context, path)? != public {
+        return Err(LindellError::SpecificError(
+            "sign context mismatch".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn check_p2_msg1(
+    msg: &Li17SignP2Msg1,
+    session_id: &[u8],
+    message_hash: &[u8],
+) -> Result<(), LindellError> {
+    check_session_id(session_id, &msg.session_id)?;
+    if msg.hash_commitment != hash_commitment(session_id, message_hash) {
+        return Err(LindellError::MessageHashMismatch);
+    }
+    Ok(())
+}
 
 pub fn li17_p2_sign1(
     context: Li17SignP2Context,
     message_hash: &Vec<u8>,
+    session_id: &[u8],
 ) -> Result<(Li17SignP2Context1, Li17SignP2Msg1), LindellError> {
     let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
         party_two::EphKeyGenFirstMsg::create_commitments();
@@ -45,71 +151,293 @@ pub fn li17_p2_sign1(
         public: context.public,
         p2_private: context.p2_private,
         p2_paillier_public: context.p2_paillier_public,
+        session_id: session_id.to_vec(),
         hash: BigInt::from_bytes(message_hash),
         p2_eph_comm_witness: eph_comm_witness,
         p2_eph_ec_key_pair: eph_ec_key_pair_party2,
     };
-    Ok((context1, eph_party_two_first_message))
+    let msg1 = Li17SignP2Msg1 {
+        session_id: session_id.to_vec(),
+        hash_commitment: hash_commitment(session_id, message_hash),
+        eph_msg: eph_party_two_first_message,
+    };
+    Ok((context1, msg1))
 }
 
 pub fn li17_p2_sign2(
     context: Li17SignP2Context1,
     msg: Li17SignP1Msg1,
 ) -> Result<Li17SignP2Msg2, LindellError> {
-    let eph_party_two_second_message =
-        party_two::EphKeyGenSecondMsg::verify_and_decommit(context.p2_eph_comm_witness, &msg)?;
+    check_session_id(&context.session_id, &msg.session_id)?;
+
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        context.p2_eph_comm_witness,
+        &msg.eph_msg,
+    )?;
 
     let partial_sig = party_two::PartialSig::compute(
         &context.p2_paillier_public.ek,
         &context.p2_paillier_public.encrypted_secret_share,
         &context.p2_private,
         &context.p2_eph_ec_key_pair,
-        &msg.public_share,
+        &msg.eph_msg.public_share,
         &context.hash,
     );
-    Ok((partial_sig, eph_party_two_second_message))
+    Ok(Li17SignP2Msg2 {
+        session_id: context.session_id,
+        partial_sig,
+        eph_msg: eph_party_two_second_message,
+    })
 }
 
 pub fn li17_p1_sign1(
-    context: Li17SignP1Context,
+    context: &Li17SignP1Context,
     msg: Li17SignP2Msg1,
     message_hash: &Vec<u8>,
+    session_id: &[u8],
 ) -> Result<(Li17SignP1Context1, Li17SignP1Msg1), LindellError> {
+    check_not_tainted(context)?;
+    check_p2_msg1(&msg, session_id, message_hash)?;
+
     let (eph_party_one_first_message, eph_ec_key_pair_party1) =
         party_one::EphKeyGenFirstMsg::create();
     let context2 = Li17SignP1Context1 {
-        public: context.public,
-        p1_private: context.p1_private,
+        public: context.public.clone(),
+        path: context.path.clone(),
+        session_id: session_id.to_vec(),
         hash: BigInt::from_bytes(message_hash),
         p1_eph_ec_key_pair: eph_ec_key_pair_party1,
-        p1_msg1_from_p2: msg,
+        p1_msg1_from_p2: msg.eph_msg,
+    };
+    let msg1 = Li17SignP1Msg1 {
+        session_id: session_id.to_vec(),
+        eph_msg: eph_party_one_first_message,
     };
-    Ok((context2, eph_party_one_first_message))
+    Ok((context2, msg1))
 }
 
+/// `root_context` is the root of the context passed to `li17_p1_sign1`, or
+/// that context itself if it was not derived.
 pub fn li17_p1_sign2(
+    root_context: &mut Li17SignP1Context,
     context: Li17SignP1Context1,
     msg: Li17SignP2Msg2,
 ) -> Result<SignatureRecid, LindellError> {
-    let (partial_sig, eph_party_two_second_message) = msg;
+    check_root_context(root_context, &context.public, &context.path)?;
+    check_session_id(&context.session_id, &msg.session_id)?;
 
     party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
         &context.p1_msg1_from_p2,
-        &eph_party_two_second_message,
+        &msg.eph_msg,
     )?;
 
     let sig = party_one::Signature::compute_with_recid(
-        &context.p1_private,
-        &partial_sig.c3,
+        &root_context.p1_private,
+        &msg.partial_sig.c3,
         &context.p1_eph_ec_key_pair,
-        &eph_party_two_second_message.comm_witness.public_share,
+        &msg.eph_msg.comm_witness.public_share,
     );
 
-    verify(&sig, &context.public, &context.hash)?;
+    verify_or_taint(root_context, &context.public, &sig, &context.hash)?;
 
     Ok(sig)
 }
 
+#[derive(Serialize, Deserialize)]
+struct Li17SignP2BatchItem {
+    hash: BigInt,
+    p2_eph_comm_witness: party_two::EphCommWitness,
+    p2_eph_ec_key_pair: party_two::EphEcKeyPair,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Li17SignP2BatchContext1 {
+    pub public: Point<Secp256k1>,
+    pub p2_private: party_two::Party2Private,
+    pub p2_paillier_public: party_two::PaillierPublic,
+    session_id: Vec<u8>,
+    items: Vec<Li17SignP2BatchItem>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Li17SignP1BatchItem {
+    hash: BigInt,
+    p1_eph_ec_key_pair: party_one::EphEcKeyPair,
+    p1_msg1_from_p2: party_two::EphKeyGenFirstMsg,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17SignP1BatchContext1 {
+    pub public: Point<Secp256k1>,
+    pub path: Vec<u32>,
+    session_id: Vec<u8>,
+    items: Vec<Li17SignP1BatchItem>,
+}
+
+/// Runs `f` on every item and fails with all the per-item errors if any.
+fn batch_map<I, T>(
+    items: impl IntoIterator<Item = I>,
+    mut f: impl FnMut(I) -> Result<T, LindellError>,
+) -> Result<Vec<T>, LindellError> {
+    let mut outputs = vec![];
+    let mut errors = vec![];
+    for (index, item) in items.into_iter().enumerate() {
+        match f(item) {
+            Ok(output) => outputs.push(output),
+            Err(err) => errors.push((index, err)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(LindellError::BatchItemErrors(errors));
+    }
+    Ok(outputs)
+}
+
+pub fn li17_p2_sign1_batch(
+    context: Li17SignP2Context,
+    message_hashes: &[Vec<u8>],
+    session_id: &[u8],
+) -> Result<(Li17SignP2BatchContext1, Vec<Li17SignP2Msg1>), LindellError> {
+    let mut items = Vec::with_capacity(message_hashes.len());
+    let mut msgs = Vec::with_capacity(message_hashes.len());
+    for message_hash in message_hashes {
+        let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
+            party_two::EphKeyGenFirstMsg::create_commitments();
+        items.push(Li17SignP2BatchItem {
+            hash: BigInt::from_bytes(message_hash),
+            p2_eph_comm_witness: eph_comm_witness,
+            p2_eph_ec_key_pair: eph_ec_key_pair_party2,
+        });
+        msgs.push(Li17SignP2Msg1 {
+            session_id: session_id.to_vec(),
+            hash_commitment: hash_commitment(session_id, message_hash),
+            eph_msg: eph_party_two_first_message,
+        });
+    }
+
+    let context1 = Li17SignP2BatchContext1 {
+        public: context.public,
+        p2_private: context.p2_private,
+        p2_paillier_public: context.p2_paillier_public,
+        session_id: session_id.to_vec(),
+        items,
+    };
+    Ok((context1, msgs))
+}
+
+pub fn li17_p2_sign2_batch(
+    context: Li17SignP2BatchContext1,
+    msgs: Vec<Li17SignP1Msg1>,
+) -> Result<Vec<Li17SignP2Msg2>, LindellError> {
+    if msgs.len() != context.items.len() {
+        return Err(LindellError::BatchLengthMismatch);
+    }
+
+    batch_map(context.items.into_iter().zip(msgs), |(item, msg)| {
+        check_session_id(&context.session_id, &msg.session_id)?;
+
+        let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+            item.p2_eph_comm_witness,
+            &msg.eph_msg,
+        )?;
+
+        let partial_sig = party_two::PartialSig::compute(
+            &context.p2_paillier_public.ek,
+            &context.p2_paillier_public.encrypted_secret_share,
+            &context.p2_private,
+            &item.p2_eph_ec_key_pair,
+            &msg.eph_msg.public_share,
+            &item.hash,
+        );
+        Ok(Li17SignP2Msg2 {
+            session_id: context.session_id.clone(),
+            partial_sig,
+            eph_msg: eph_party_two_second_message,
+        })
+    })
+}
+
+pub fn li17_p1_sign1_batch(
+    context: &Li17SignP1Context,
+    msgs: Vec<Li17SignP2Msg1>,
+    message_hashes: &[Vec<u8>],
+    session_id: &[u8],
+) -> Result<(Li17SignP1BatchContext1, Vec<Li17SignP1Msg1>), LindellError> {
+    check_not_tainted(context)?;
+    if msgs.len() != message_hashes.len() {
+        return Err(LindellError::BatchLengthMismatch);
+    }
+
+    let msgs = batch_map(
+        msgs.into_iter().zip(message_hashes),
+        |(msg, message_hash)| {
+            check_p2_msg1(&msg, session_id, message_hash)?;
+            Ok(msg)
+        },
+    )?;
+
+    let mut items = Vec::with_capacity(msgs.len());
+    let mut msgs1 = Vec::with_capacity(msgs.len());
+    for (msg, message_hash) in msgs.into_iter().zip(message_hashes) {
+        let (eph_party_one_first_message, eph_ec_key_pair_party1) =
+            party_one::EphKeyGenFirstMsg::create();
+        items.push(Li17SignP1BatchItem {
+            hash: BigInt::from_bytes(message_hash),
+            p1_eph_ec_key_pair: eph_ec_key_pair_party1,
+            p1_msg1_from_p2: msg.eph_msg,
+        });
+        msgs1.push(Li17SignP1Msg1 {
+            session_id: session_id.to_vec(),
+            eph_msg: eph_party_one_first_message,
+        });
+    }
+
+    let context1 = Li17SignP1BatchContext1 {
+        public: context.public.clone(),
+        path: context.path.clone(),
+        session_id: session_id.to_vec(),
+        items,
+    };
+    Ok((context1, msgs1))
+}
+
+/// Fails with every item that does not pass the checks made before
+/// decryption. Once decryption starts, the first signature that fails to
+/// verify taints `root_context` and stops the batch with `SignatureAbort`, so
+/// P2 learns at most one bit from it.
+pub fn li17_p1_sign2_batch(
+    root_context: &mut Li17SignP1Context,
+    context: Li17SignP1BatchContext1,
+    msgs: Vec<Li17SignP2Msg2>,
+) -> Result<Vec<SignatureRecid>, LindellError> {
+    check_root_context(root_context, &context.public, &context.path)?;
+    if msgs.len() != context.items.len() {
+        return Err(LindellError::BatchLengthMismatch);
+    }
+
+    let msgs = batch_map(context.items.iter().zip(msgs), |(item, msg)| {
+        check_session_id(&context.session_id, &msg.session_id)?;
+        party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
+            &item.p1_msg1_from_p2,
+            &msg.eph_msg,
+        )?;
+        Ok(msg)
+    })?;
+
+    let mut sigs = Vec::with_capacity(msgs.len());
+    for (item, msg) in context.items.iter().zip(msgs) {
+        let sig = party_one::Signature::compute_with_recid(
+            &root_context.p1_private,
+            &msg.partial_sig.c3,
+            &item.p1_eph_ec_key_pair,
+            &msg.eph_msg.comm_witness.public_share,
+        );
+        verify_or_taint(root_context, &context.public, &sig, &item.hash)?;
+        sigs.push(sig);
+    }
+    Ok(sigs)
+}
+
 pub fn verify(
     sig: &SignatureRecid,
     y: &Point<Secp256k1>,
@@ -117,7 +445,8 @@ pub fn verify(
 ) -> Result<(), LindellError> {
     let r = Scalar::<Secp256k1>::from(&sig.r);
     let s = Scalar::<Secp256k1>::from(&sig.s);
-    let b = s.invert().unwrap();
+    // s = 0 can be forced by the other party, and must not panic
+    let b = s.invert().ok_or(LindellError::SignatureAbort)?;
     let a = Scalar::<Secp256k1>::from(message_hash);
     let u1 = a * &b;
     let u2 = &r * &b;
@@ -126,12 +455,8 @@ pub fn verify(
     let gu1 = g * u1;
     let yu2 = y * &u2;
     // can be faster using shamir trick
-    if r == Scalar::<Secp256k1>::from(
-        &(gu1 + yu2)
-            .x_coord()
-            .unwrap()
-            .mod_floor(Scalar::<Secp256k1>::group_order()),
-    ) {
+    let x = (gu1 + yu2).x_coord().ok_or(LindellError::SignatureAbort)?;
+    if r == Scalar::<Secp256k1>::from(&x.mod_floor(Scalar::<Secp256k1>::group_order())) {
         Ok(())
     } else {
         Err(LindellError::SpecificError("verify failed".to_string()))
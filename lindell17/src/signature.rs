@@ -0,0 +1,234 @@
+use curv::{
+    arithmetic::traits::*,
+    elliptic::curves::{Scalar, Secp256k1},
+    BigInt,
+};
+use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
+use serde::{Deserialize, Serialize};
+
+use crate::error::LindellError;
+
+/// An ECDSA signature with recovery id, always kept in low-s form (EIP-2 / BIP-62).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Li17Signature {
+    pub r: BigInt,
+    pub s: BigInt,
+    pub recid: u8,
+}
+
+fn to_bytes32(value: &BigInt) -> Result<[u8; 32], LindellError> {
+    let bytes = value.to_bytes();
+    if bytes.len() > 32 {
+        return Err(LindellError::InvalidSignatureEncoding(
+            "integer longer than 32 bytes".to_string(),
+        ));
+    }
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+fn der_integer(value: &BigInt) -> Vec<u8> {
+    let mut bytes = value.to_bytes();
+    if bytes.is_empty() {
+        bytes.push(0);
+    }
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+    let mut out = vec![0x02, bytes.len() as u8];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+fn parse_der_integer(bytes: &[u8]) -> Result<(BigInt, &[u8]), LindellError> {
+    let err = |msg: &str| LindellError::InvalidSignatureEncoding(msg.to_string());
+    if bytes.len() < 2 || bytes[0] != 0x02 {
+        return Err(err("expected der integer"));
+    }
+    let len = usize::from(bytes[1]);
+    if len == 0 || len > 33 || bytes.len() < 2 + len {
+        return Err(err("bad der integer length"));
+    }
+    let value = &bytes[2..2 + len];
+    if value[0] & 0x80 != 0 {
+        return Err(err("negative der integer"));
+    }
+    if len > 1 && value[0] == 0 && value[1] & 0x80 == 0 {
+        return Err(err("non-minimal der integer"));
+    }
+    Ok((BigInt::from_bytes(value), &bytes[2 + len..]))
+}
+
+fn chain_id_too_large() -> LindellError {
+    LindellError::InvalidSignatureEncoding("chain id too large".to_string())
+}
+
+/// `35 + 2 * chain_id`, the EIP-155 `v` for recovery id 0.
+fn eip155_offset(chain_id: u64) -> Result<u64, LindellError> {
+    chain_id
+        .checked_mul(2)
+        .and_then(|v| v.checked_add(35))
+        .ok_or_else(chain_id_too_large)
+}
+
+impl Li17Signature {
+    /// Builds a signature from its components, normalizing to low-s and
+    /// flipping the recovery id to match.
+    pub fn new(r: BigInt, s: BigInt, recid: u8) -> Result<Self, LindellError> {
+        let q = Scalar::<Secp256k1>::group_order();
+        let zero = BigInt::from(0);
+        if r <= zero || &r >= q || s <= zero || &s >= q {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "r or s out of range".to_string(),
+            ));
+        }
+        if recid > 3 {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "recovery id out of range".to_string(),
+            ));
+        }
+        let half_q = q.div_floor(&BigInt::from(2));
+        if s > half_q {
+            Ok(Li17Signature {
+                r,
+                s: q - &s,
+                recid: recid ^ 1,
+            })
+        } else {
+            Ok(Li17Signature { r, s, recid })
+        }
+    }
+
+    pub fn from_recid(sig: &SignatureRecid) -> Result<Self, LindellError> {
+        Self::new(sig.r.clone(), sig.s.clone(), sig.recid)
+    }
+
+    pub fn to_recid(&self) -> SignatureRecid {
+        SignatureRecid {
+            r: self.r.clone(),
+            s: self.s.clone(),
+            recid: self.recid,
+        }
+    }
+
+    /// Legacy Ethereum `v`, i.e. `27 + recid`.
+    pub fn v(&self) -> u8 {
+        27 + self.recid
+    }
+
+    /// EIP-155 `v`, i.e. `recid + 35 + 2 * chain_id`.
+    pub fn eip155_v(&self, chain_id: u64) -> Result<u64, LindellError> {
+        eip155_offset(chain_id)?
+            .checked_add(u64::from(self.recid))
+            .ok_or_else(chain_id_too_large)
+    }
+
+    /// 64-byte `r || s`.
+    pub fn to_compact(&self) -> Result<[u8; 64], LindellError> {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&to_bytes32(&self.r)?);
+        out[32..].copy_from_slice(&to_bytes32(&self.s)?);
+        Ok(out)
+    }
+
+    pub fn from_compact(bytes: &[u8], recid: u8) -> Result<Self, LindellError> {
+        if bytes.len() != 64 {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "compact signature must be 64 bytes".to_string(),
+            ));
+        }
+        Self::new(
+            BigInt::from_bytes(&bytes[..32]),
+            BigInt::from_bytes(&bytes[32..]),
+            recid,
+        )
+    }
+
+    /// 65-byte `r || s || v` with `v = 27 + recid`.
+    pub fn to_rsv(&self) -> Result<[u8; 65], LindellError> {
+        let mut out = [0u8; 65];
+        out[..64].copy_from_slice(&self.to_compact()?);
+        out[64] = self.v();
+        Ok(out)
+    }
+
+    /// Parses `r || s || v`, accepting `v` as either `0/1` or `27/28`.
+    pub fn from_rsv(bytes: &[u8]) -> Result<Self, LindellError> {
+        if bytes.len() != 65 {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "rsv signature must be 65 bytes".to_string(),
+            ));
+        }
+        let recid = match bytes[64] {
+            0 | 1 => bytes[64],
+            27 | 28 => bytes[64] - 27,
+            _ => {
+                return Err(LindellError::InvalidSignatureEncoding(
+                    "bad v value".to_string(),
+                ))
+            }
+        };
+        Self::from_compact(&bytes[..64], recid)
+    }
+
+    /// `r || s || v` with the EIP-155 `v` in minimal big-endian form.
+    pub fn to_eip155(&self, chain_id: u64) -> Result<Vec<u8>, LindellError> {
+        let mut out = self.to_compact()?.to_vec();
+        let v = self.eip155_v(chain_id)?.to_be_bytes();
+        let first = v.iter().position(|b| *b != 0).unwrap_or(v.len() - 1);
+        out.extend_from_slice(&v[first..]);
+        Ok(out)
+    }
+
+    pub fn from_eip155(bytes: &[u8], chain_id: u64) -> Result<Self, LindellError> {
+        if bytes.len() <= 64 || bytes.len() > 64 + 8 {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "bad eip155 signature length".to_string(),
+            ));
+        }
+        let v = bytes[64..]
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+        let recid = v
+            .checked_sub(eip155_offset(chain_id)?)
+            .filter(|recid| *recid <= 1)
+            .ok_or_else(|| {
+                LindellError::InvalidSignatureEncoding("v does not match chain id".to_string())
+            })?;
+        Self::from_compact(&bytes[..64], recid as u8)
+    }
+
+    /// DER `SEQUENCE { INTEGER r, INTEGER s }`.
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut body = der_integer(&self.r);
+        body.extend(der_integer(&self.s));
+        let mut out = vec![0x30, body.len() as u8];
+        out.extend(body);
+        out
+    }
+
+    pub fn from_der(bytes: &[u8], recid: u8) -> Result<Self, LindellError> {
+        if bytes.len() < 2 || bytes[0] != 0x30 || usize::from(bytes[1]) != bytes.len() - 2 {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "bad der sequence".to_string(),
+            ));
+        }
+        let (r, rest) = parse_der_integer(&bytes[2..])?;
+        let (s, rest) = parse_der_integer(rest)?;
+        if !rest.is_empty() {
+            return Err(LindellError::InvalidSignatureEncoding(
+                "trailing bytes after der signature".to_string(),
+            ));
+        }
+        Self::new(r, s, recid)
+    }
+}
+
+impl TryFrom<SignatureRecid> for Li17Signature {
+    type Error = LindellError;
+
+    fn try_from(sig: SignatureRecid) -> Result<Self, Self::Error> {
+        Self::new(sig.r, sig.s, sig.recid)
+    }
+}
@@ -1,12 +1,51 @@
 #[cfg(test)]
 mod tests {
+    use curv::{
+        arithmetic::traits::*,
+        elliptic::curves::{Point, Scalar, Secp256k1},
+        BigInt,
+    };
+    use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one;
+    use paillier::{
+        Decrypt, Encrypt, EncryptWithChosenRandomness, EncryptionKey, Paillier, Randomness,
+        RawCiphertext, RawPlaintext,
+    };
+    use serde::{de::DeserializeOwned, Serialize};
     use sha2::{Digest, Sha256};
+    use zk_paillier::zkproofs::DLogStatement;
 
     use crate::{
+        address::{
+            btc_p2pkh_address, btc_p2wpkh_address, eth_checksum_address, public_key_compressed,
+            public_key_uncompressed, BtcNetwork,
+        },
+        derive::{derive_child_public, li17_p1_derive_child, li17_p2_derive_child},
+        eip712::{hash_typed_data, TypedData},
+        error::LindellError,
         keygen::{li17_p1_key_gen1, li17_p1_key_gen2, li17_p2_key_gen1, li17_p2_key_gen2},
-        sign::{li17_p1_sign1, li17_p1_sign2, li17_p2_sign1, li17_p2_sign2},
+        modulus::{
+            blum_paillier_keypair, blum_paillier_keypair_with_modulus_size, check_modulus_size,
+            li17_p1_paillier_key_pair, ring_pedersen_setup, verify_paillier_modulus,
+            verify_ring_pedersen, NoSmallFactorProof, PaillierBlumModulusProof, PAILLIER_MAX_BITS,
+        },
+        presign::{
+            li17_p1_presign1, li17_p1_presign_sign, li17_p2_presign1, li17_p2_presign2,
+            li17_p2_presign_sign, Li17Presignature, Li17PresignatureStore,
+        },
+        refresh::{li17_p1_refresh1, li17_p1_refresh2, li17_p2_refresh1, li17_p2_refresh2},
+        sign::{
+            li17_p1_sign1, li17_p1_sign1_batch, li17_p1_sign2, li17_p1_sign2_batch, li17_p2_sign1,
+            li17_p2_sign1_batch, li17_p2_sign2, li17_p2_sign2_batch, verify,
+        },
+        signature::Li17Signature,
     };
 
+    const SESSION_ID: &[u8] = b"test session";
+
+    fn serde_clone<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap()
+    }
+
     #[test]
     fn sign() {
         // keygen
@@ -14,21 +53,607 @@ mod tests {
 
         let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
 
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, _pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        // sign
+        let mut hasher = Sha256::new();
+        hasher.update(b"random message");
+        let hash = hasher.finalize().to_vec();
+
+        let (context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash, SESSION_ID).unwrap();
+
+        let (context1p1, smsg1p1) =
+            li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+
+        let smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+
+        let _sig = li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2).unwrap();
+    }
+
+    #[test]
+    fn sign_rejects_mismatched_session() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
         let (sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
 
         let (sign_context_p2, _pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
 
+        let hash = Sha256::digest(b"random message").to_vec();
+        let other_hash = Sha256::digest(b"other message").to_vec();
+
+        let (_context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash, SESSION_ID).unwrap();
+
+        match li17_p1_sign1(
+            sign_context_p1.clone(),
+            smsg1p2.clone(),
+            &hash,
+            b"other session",
+        ) {
+            Err(LindellError::SessionIdMismatch) => {}
+            _ => panic!("expected SessionIdMismatch"),
+        }
+        match li17_p1_sign1(&sign_context_p1, smsg1p2, &other_hash, SESSION_ID) {
+            Err(LindellError::MessageHashMismatch) => {}
+            _ => panic!("expected MessageHashMismatch"),
+        }
+    }
+
+    #[test]
+    fn keygen_rejects_bad_pdl_proof() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (_sign_context_p1, mut msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+        msg2p1.6 = BigInt::from(1);
+
+        match li17_p2_key_gen2(context1p2, msg2p1) {
+            Err(LindellError::InvalidPdlProof) => {}
+            _ => panic!("expected InvalidPdlProof"),
+        }
+    }
+
+    #[test]
+    fn paillier_modulus_proofs() {
+        let (ek, dk) = blum_paillier_keypair();
+        let (ring_pedersen, ring_pedersen_proof) = ring_pedersen_setup();
+        verify_ring_pedersen(&ring_pedersen, &ring_pedersen_proof).unwrap();
+
+        // degenerate generators and foreign parameters are refused
+        for ni in [BigInt::from(0), BigInt::one(), ring_pedersen.g.clone()] {
+            let statement = DLogStatement {
+                ni,
+                ..ring_pedersen.clone()
+            };
+            match verify_ring_pedersen(&statement, &ring_pedersen_proof) {
+                Err(LindellError::InvalidRingPedersenProof) => {}
+                _ => panic!("expected InvalidRingPedersenProof"),
+            }
+        }
+        let (other_ring_pedersen, _) = ring_pedersen_setup();
+        assert!(verify_ring_pedersen(&other_ring_pedersen, &ring_pedersen_proof).is_err());
+
+        let blum_proof = PaillierBlumModulusProof::prove(&ek, &dk).unwrap();
+        let no_small_factor_proof = NoSmallFactorProof::prove(&ek, &dk, &ring_pedersen).unwrap();
+        verify_paillier_modulus(&ek, &blum_proof, &no_small_factor_proof, &ring_pedersen).unwrap();
+
+        let (other_ek, _) = blum_paillier_keypair();
+        assert!(blum_proof.verify(&other_ek).is_err());
+        assert!(no_small_factor_proof
+            .verify(&other_ek, &ring_pedersen)
+            .is_err());
+
+        let (small_ek, small_dk) = blum_paillier_keypair_with_modulus_size(1024);
+        let small_ring_pedersen = DLogStatement {
+            N: small_ek.n.clone(),
+            ..ring_pedersen.clone()
+        };
+        match verify_ring_pedersen(&small_ring_pedersen, &ring_pedersen_proof) {
+            Err(LindellError::RingPedersenModulusTooSmall(1024)) => {}
+            _ => panic!("expected RingPedersenModulusTooSmall"),
+        }
+        let blum_proof = PaillierBlumModulusProof::prove(&small_ek, &small_dk).unwrap();
+        let no_small_factor_proof =
+            NoSmallFactorProof::prove(&small_ek, &small_dk, &ring_pedersen).unwrap();
+        match verify_paillier_modulus(
+            &small_ek,
+            &blum_proof,
+            &no_small_factor_proof,
+            &ring_pedersen,
+        ) {
+            Err(LindellError::PaillierModulusTooSmall(1024)) => {}
+            _ => panic!("expected PaillierModulusTooSmall"),
+        }
+
+        let large_n = BigInt::one() << PAILLIER_MAX_BITS;
+        let large_ring_pedersen = DLogStatement {
+            N: large_n.clone(),
+            ..ring_pedersen.clone()
+        };
+        match verify_ring_pedersen(&large_ring_pedersen, &ring_pedersen_proof) {
+            Err(LindellError::RingPedersenModulusTooLarge(bits)) => {
+                assert_eq!(bits, PAILLIER_MAX_BITS + 1)
+            }
+            _ => panic!("expected RingPedersenModulusTooLarge"),
+        }
+        match check_modulus_size(&EncryptionKey::from(&large_n)) {
+            Err(LindellError::PaillierModulusTooLarge(bits)) => {
+                assert_eq!(bits, PAILLIER_MAX_BITS + 1)
+            }
+            _ => panic!("expected PaillierModulusTooLarge"),
+        }
+    }
+
+    #[test]
+    fn paillier_key_pair_round_trip() {
+        let (_, _, ec_key_pair) = party_one::KeyGenFirstMsg::create_commitments();
+        let (paillier_key_pair, dk) = li17_p1_paillier_key_pair(&ec_key_pair).unwrap();
+
+        // the fork has no constructor, so pin the serde layout it is built through
+        let value = serde_json::to_value(&paillier_key_pair).unwrap();
+        let mut fields: Vec<&String> = value.as_object().unwrap().keys().collect();
+        fields.sort();
+        assert_eq!(fields, ["dk", "ek", "encrypted_share", "randomness"]);
+        assert_eq!(value["dk"], serde_json::to_value(&dk).unwrap());
+
+        let ek: EncryptionKey = serde_json::from_value(value["ek"].clone()).unwrap();
+        let encrypted_share: BigInt =
+            serde_json::from_value(value["encrypted_share"].clone()).unwrap();
+        let randomness: BigInt = serde_json::from_value(value["randomness"].clone()).unwrap();
+        let secret_share: Scalar<Secp256k1> = serde_json::from_value(
+            serde_json::to_value(&ec_key_pair).unwrap()["secret_share"].clone(),
+        )
+        .unwrap();
+
+        let plaintext: BigInt =
+            Paillier::decrypt(&dk, RawCiphertext::from(encrypted_share.clone())).into();
+        assert_eq!(plaintext, secret_share.to_bigint());
+        let reencrypted = Paillier::encrypt_with_chosen_randomness(
+            &ek,
+            RawPlaintext::from(secret_share.to_bigint()),
+            &Randomness(randomness),
+        );
+        assert_eq!(reencrypted.0.into_owned(), encrypted_share);
+    }
+
+    #[test]
+    fn refresh() {
+        // keygen
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        // refresh
+        let (rcontext1p1, rmsg1p1) = li17_p1_refresh1(sign_context_p1).unwrap();
+
+        let (rcontext1p2, rmsg1p2) = li17_p2_refresh1(sign_context_p2, rmsg1p1).unwrap();
+
+        let (mut sign_context_p1, rmsg2p1) = li17_p1_refresh2(rcontext1p1, rmsg1p2).unwrap();
+
+        let sign_context_p2 = li17_p2_refresh2(rcontext1p2, rmsg2p1).unwrap();
+
+        assert!(sign_context_p1.public == pk);
+        assert!(sign_context_p2.public == pk);
+        assert!(sign_context_p1.public_p1 == sign_context_p2.public_p1);
+        assert!(sign_context_p1.public_p2 == sign_context_p2.public_p2);
+
         // sign
         let mut hasher = Sha256::new();
         hasher.update(b"random message");
         let hash = hasher.finalize().to_vec();
 
-        let (context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash).unwrap();
+        let (context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash, SESSION_ID).unwrap();
+
+        let (context1p1, smsg1p1) =
+            li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+
+        let smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+
+        let _sig = li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2).unwrap();
+    }
+
+    #[test]
+    fn signature_encodings() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"random message");
+        let hash = hasher.finalize().to_vec();
+
+        let (context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash, SESSION_ID).unwrap();
+
+        let (context1p1, smsg1p1) =
+            li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+
+        let smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+
+        let sig = li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2).unwrap();
+
+        // the high-s twin must normalize to the same signature
+        let q = Scalar::<Secp256k1>::group_order();
+        let high = Li17Signature::new(sig.r.clone(), q - &sig.s, sig.recid ^ 1).unwrap();
+        let low = Li17Signature::from_recid(&sig).unwrap();
+        assert_eq!(high, low);
+        assert!(low.s <= q.div_floor(&BigInt::from(2)));
+        verify(&low.to_recid(), &pk, &BigInt::from_bytes(&hash)).unwrap();
+
+        let rsv = low.to_rsv().unwrap();
+        assert_eq!(rsv[64], 27 + low.recid);
+        assert_eq!(Li17Signature::from_rsv(&rsv).unwrap(), low);
+
+        let eip155 = low.to_eip155(1).unwrap();
+        assert_eq!(eip155[64], 37 + low.recid);
+        assert_eq!(Li17Signature::from_eip155(&eip155, 1).unwrap(), low);
+        assert!(Li17Signature::from_eip155(&eip155, 5).is_err());
+        assert!(low.to_eip155(u64::MAX).is_err());
+        assert!(Li17Signature::from_eip155(&eip155, u64::MAX).is_err());
+
+        let compact = low.to_compact().unwrap();
+        assert_eq!(
+            Li17Signature::from_compact(&compact, low.recid).unwrap(),
+            low
+        );
+
+        let der = low.to_der();
+        assert_eq!(Li17Signature::from_der(&der, low.recid).unwrap(), low);
+    }
+
+    #[test]
+    fn address() {
+        // public key of the secret key 1
+        let public = Point::<Secp256k1>::generator().to_point();
+
+        assert_eq!(public_key_compressed(&public).len(), 33);
+        assert_eq!(public_key_uncompressed(&public)[0], 0x04);
+        assert_eq!(
+            eth_checksum_address(&public),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+        assert_eq!(
+            btc_p2pkh_address(&public, BtcNetwork::Mainnet),
+            "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"
+        );
+        assert_eq!(
+            btc_p2wpkh_address(&public, BtcNetwork::Mainnet).unwrap(),
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        );
+    }
+
+    #[test]
+    fn eip712() {
+        let typed_data = TypedData::from_json(
+            r#"{
+                "types": {
+                    "EIP712Domain": [
+                        { "name": "name", "type": "string" },
+                        { "name": "version", "type": "string" },
+                        { "name": "chainId", "type": "uint256" },
+                        { "name": "verifyingContract", "type": "address" }
+                    ],
+                    "Person": [
+                        { "name": "name", "type": "string" },
+                        { "name": "wallet", "type": "address" }
+                    ],
+                    "Mail": [
+                        { "name": "from", "type": "Person" },
+                        { "name": "to", "type": "Person" },
+                        { "name": "contents", "type": "string" }
+                    ]
+                },
+                "primaryType": "Mail",
+                "domain": {
+                    "name": "Ether Mail",
+                    "version": "1",
+                    "chainId": 1,
+                    "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+                },
+                "message": {
+                    "from": { "name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826" },
+                    "to": { "name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB" },
+                    "contents": "Hello, Bob!"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            typed_data.encode_type("Mail").unwrap(),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(typed_data.domain_separator().unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(hash_typed_data(&typed_data).unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn derive_child() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        assert_eq!(sign_context_p1.chain_code, sign_context_p2.chain_code);
+
+        let path = [44, 60, 0, 7];
+        let child_p1 = li17_p1_derive_child(&sign_context_p1, &path).unwrap();
+        let child_p2 = li17_p2_derive_child(&sign_context_p2, &path).unwrap();
+
+        let (child_pk, _, child_chain_code) =
+            derive_child_public(&pk, &sign_context_p1.chain_code, &path).unwrap();
+        assert!(child_p1.public == child_pk);
+        assert!(child_p2.public == child_pk);
+        assert!(child_pk != pk);
+        assert_eq!(child_p1.chain_code, child_chain_code);
+        assert!(derive_child_public(&pk, &sign_context_p1.chain_code, &[1 << 31]).is_err());
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"random message");
+        let hash = hasher.finalize().to_vec();
+
+        let (context1p2, smsg1p2) =
+            li17_p2_sign1(serde_clone(&child_p2), &hash, SESSION_ID).unwrap();
 
-        let (context1p1, smsg1p1) = li17_p1_sign1(sign_context_p1, smsg1p2, &hash).unwrap();
+        let (context1p1, smsg1p1) = li17_p1_sign1(&child_p1, smsg1p2, &hash, SESSION_ID).unwrap();
 
         let smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
 
-        let _sig = li17_p1_sign2(context1p1, smsg2p2).unwrap();
+        // the child context cannot finish a signature, the root does
+        assert!(li17_p1_sign2(
+            &mut serde_clone(&child_p1),
+            serde_clone(&context1p1),
+            serde_clone(&smsg2p2)
+        )
+        .is_err());
+        let sig = li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2).unwrap();
+        verify(&sig, &child_pk, &BigInt::from_bytes(&hash)).unwrap();
+
+        // a bad signature for a derived key taints the root
+        let (context1p2, smsg1p2) = li17_p2_sign1(child_p2, &hash, SESSION_ID).unwrap();
+        let (context1p1, smsg1p1) = li17_p1_sign1(&child_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+        let mut smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+        smsg2p2.partial_sig.c3 = BigInt::from(12345);
+        match li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2) {
+            Err(LindellError::SignatureAbort) => {}
+            _ => panic!("expected SignatureAbort"),
+        }
+        assert!(sign_context_p1.tainted);
+
+        assert_eq!(child_p1.path, path);
+        match li17_p1_refresh1(child_p1) {
+            Err(LindellError::DeriveError(_)) => {}
+            _ => panic!("expected DeriveError"),
+        }
+    }
+
+    #[test]
+    fn presign() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (mut sign_context_p2, pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        // offline
+        let mut store_p1 = Li17PresignatureStore::new();
+        let mut store_p2 = Li17PresignatureStore::new();
+        for _ in 0..2 {
+            let (pcontext1p2, pmsg1p2) = li17_p2_presign1().unwrap();
+            let (presig_p1, pmsg1p1) = li17_p1_presign1(&mut sign_context_p1, pmsg1p2).unwrap();
+            let presig_p2 = li17_p2_presign2(pcontext1p2, pmsg1p1).unwrap();
+            assert_eq!(presig_p1.id(), presig_p2.id());
+            store_p1.insert(presig_p1).unwrap();
+            store_p2.insert(presig_p2).unwrap();
+        }
+
+        // online
+        let mut hasher = Sha256::new();
+        hasher.update(b"random message");
+        let hash = hasher.finalize().to_vec();
+
+        let id = store_p2.ids()[0].clone();
+        let presig_p2 = store_p2.take(&id).unwrap();
+        let presig_p2_copy = serde_clone(&presig_p2);
+        let presig_p2_reinserted = serde_clone(&presig_p2);
+        let smsg2p2 = li17_p2_presign_sign(&mut sign_context_p2, &[], presig_p2, &hash).unwrap();
+        let smsg2p2_copy = serde_clone(&smsg2p2);
+        let presig_p1 = store_p1.take(&smsg2p2.presignature_id).unwrap();
+        let presig_p1_copy = serde_clone(&presig_p1);
+        let sig =
+            li17_p1_presign_sign(&mut sign_context_p1, &[], presig_p1, smsg2p2, &hash).unwrap();
+        verify(&sig, &pk, &BigInt::from_bytes(&hash)).unwrap();
+
+        // copies that bypass the store are refused by the signing contexts
+        let other_hash = Sha256::digest(b"other message").to_vec();
+        match li17_p2_presign_sign(&mut sign_context_p2, &[], presig_p2_copy, &other_hash) {
+            Err(LindellError::PresignatureUsed(_)) => {}
+            _ => panic!("expected PresignatureUsed"),
+        }
+        match li17_p1_presign_sign(
+            &mut sign_context_p1,
+            &[],
+            presig_p1_copy,
+            smsg2p2_copy,
+            &hash,
+        ) {
+            Err(LindellError::PresignatureUsed(_)) => {}
+            _ => panic!("expected PresignatureUsed"),
+        }
+
+        match store_p2.insert(presig_p2_reinserted) {
+            Err(LindellError::PresignatureUsed(_)) => {}
+            _ => panic!("expected PresignatureUsed"),
+        }
+        match store_p1.take(&id) {
+            Err(LindellError::PresignatureNotFound(_)) => {}
+            _ => panic!("expected PresignatureNotFound"),
+        }
+        assert_eq!(store_p1.len(), 1);
+
+        // presignatures are used in order, an older one is dropped
+        let (pcontext1p2, pmsg1p2) = li17_p2_presign1().unwrap();
+        let (presig_p1, pmsg1p1) = li17_p1_presign1(&mut sign_context_p1, pmsg1p2).unwrap();
+        let presig_p2 = li17_p2_presign2(pcontext1p2, pmsg1p1).unwrap();
+        let older = store_p2.ids()[0].clone();
+        let newer = presig_p2.id().to_string();
+        store_p1.insert(presig_p1).unwrap();
+        store_p2.insert(presig_p2).unwrap();
+        store_p2.take(&newer).unwrap();
+        store_p1.take(&newer).unwrap();
+        assert!(store_p1.is_empty() && store_p2.is_empty());
+        match store_p2.take(&older) {
+            Err(LindellError::PresignatureNotFound(_)) => {}
+            _ => panic!("expected PresignatureNotFound"),
+        }
+    }
+
+    #[test]
+    fn sign_batch() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, pk) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        let hashes: Vec<Vec<u8>> = (0..3u8)
+            .map(|i| {
+                let mut hasher = Sha256::new();
+                hasher.update([i]);
+                hasher.finalize().to_vec()
+            })
+            .collect();
+
+        let (context1p2, smsgs1p2) =
+            li17_p2_sign1_batch(sign_context_p2, &hashes, SESSION_ID).unwrap();
+
+        // every bad item is reported, not just the first
+        let mut bad_msgs = smsgs1p2.clone();
+        bad_msgs[0].session_id = b"other session".to_vec();
+        bad_msgs[2].session_id = b"other session".to_vec();
+        match li17_p1_sign1_batch(&sign_context_p1, bad_msgs, &hashes, SESSION_ID) {
+            Err(LindellError::BatchItemErrors(errors)) => {
+                let indexes: Vec<_> = errors.iter().map(|(index, _)| *index).collect();
+                assert_eq!(indexes, vec![0, 2]);
+            }
+            _ => panic!("expected BatchItemErrors"),
+        }
+
+        let (context1p1, smsgs1p1) =
+            li17_p1_sign1_batch(&sign_context_p1, smsgs1p2, &hashes, SESSION_ID).unwrap();
+
+        let smsgs2p2 = li17_p2_sign2_batch(context1p2, smsgs1p1).unwrap();
+
+        let mut bad_msgs = serde_clone(&smsgs2p2);
+        let sigs = li17_p1_sign2_batch(&mut sign_context_p1, context1p1.clone(), smsgs2p2).unwrap();
+        assert_eq!(sigs.len(), hashes.len());
+        for (sig, hash) in sigs.iter().zip(&hashes) {
+            verify(sig, &pk, &BigInt::from_bytes(hash)).unwrap();
+        }
+
+        match li17_p1_sign2_batch(&mut sign_context_p1, context1p1.clone(), vec![]) {
+            Err(LindellError::BatchLengthMismatch) => {}
+            _ => panic!("expected BatchLengthMismatch"),
+        }
+
+        // a bad signature stops the batch, it does not report every item
+        bad_msgs[0].partial_sig.c3 = BigInt::from(12345);
+        bad_msgs[2].partial_sig.c3 = BigInt::from(12345);
+        match li17_p1_sign2_batch(&mut sign_context_p1, context1p1, bad_msgs) {
+            Err(LindellError::SignatureAbort) => {}
+            _ => panic!("expected SignatureAbort"),
+        }
+        assert!(sign_context_p1.tainted);
+    }
+
+    #[test]
+    fn taint_on_zero_signature() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+        let (sign_context_p2, _) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        let hash = Sha256::digest(b"random message").to_vec();
+        let ek = sign_context_p2.p2_paillier_public.ek.clone();
+        let (context1p2, smsg1p2) = li17_p2_sign1(sign_context_p2, &hash, SESSION_ID).unwrap();
+        let (context1p1, smsg1p1) =
+            li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+        let mut smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+
+        // c3 = Enc(0) makes s = 0, which must abort instead of panicking
+        smsg2p2.partial_sig.c3 = Paillier::encrypt(&ek, RawPlaintext::from(BigInt::zero()))
+            .0
+            .into_owned();
+        match li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2) {
+            Err(LindellError::SignatureAbort) => {}
+            _ => panic!("expected SignatureAbort"),
+        }
+        assert!(sign_context_p1.tainted);
+    }
+
+    #[test]
+    fn taint_on_signature_abort() {
+        let (context1p1, msg1p1) = li17_p1_key_gen1().unwrap();
+
+        let (context1p2, msg1p2) = li17_p2_key_gen1(msg1p1).unwrap();
+
+        let (mut sign_context_p1, msg2p1) = li17_p1_key_gen2(context1p1, msg1p2).unwrap();
+
+        let (sign_context_p2, _) = li17_p2_key_gen2(context1p2, msg2p1).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"random message");
+        let hash = hasher.finalize().to_vec();
+
+        let (context1p2, smsg1p2) =
+            li17_p2_sign1(serde_clone(&sign_context_p2), &hash, SESSION_ID).unwrap();
+
+        let (context1p1, smsg1p1) =
+            li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID).unwrap();
+
+        let mut smsg2p2 = li17_p2_sign2(context1p2, smsg1p1).unwrap();
+        smsg2p2.partial_sig.c3 = BigInt::from(12345);
+
+        match li17_p1_sign2(&mut sign_context_p1, context1p1, smsg2p2) {
+            Err(LindellError::SignatureAbort) => {}
+            _ => panic!("expected SignatureAbort"),
+        }
+        assert!(sign_context_p1.tainted);
+
+        let (_, smsg1p2) = li17_p2_sign1(serde_clone(&sign_context_p2), &hash, SESSION_ID).unwrap();
+        match li17_p1_sign1(&sign_context_p1, smsg1p2, &hash, SESSION_ID) {
+            Err(LindellError::KeyTainted) => {}
+            _ => panic!("expected KeyTainted"),
+        }
+
+        // P2 knows the refresh factor, so a refresh would not undo the leak
+        match li17_p1_refresh1(sign_context_p1) {
+            Err(LindellError::KeyTainted) => {}
+            _ => panic!("expected KeyTainted"),
+        }
     }
 }
@@ -0,0 +1,35 @@
+use curv::elliptic::curves::{Ed25519, Point};
+
+use crate::{error::TssError, keygen::SignKey, TssResult};
+
+/// Raw 32-byte compressed Ed25519 public key.
+pub fn pubkey_bytes(pubkey: &Point<Ed25519>) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&pubkey.to_bytes(true));
+    out
+}
+
+/// Base58 encoding of the 32-byte public key, as used for Solana addresses.
+pub fn pubkey_base58(pubkey: &Point<Ed25519>) -> String {
+    bs58::encode(pubkey_bytes(pubkey)).into_string()
+}
+
+pub fn pubkey_from_base58(address: &str) -> TssResult<Point<Ed25519>> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|e| TssError::SpecificError(e.to_string()))?;
+    if bytes.len() != 32 {
+        return Err(TssError::SpecificError("invalid pubkey length".into()));
+    }
+    Point::from_bytes(&bytes).map_err(|e| TssError::SpecificError(format!("{:?}", e)))
+}
+
+impl SignKey {
+    pub fn agg_pubkey_bytes(&self) -> [u8; 32] {
+        pubkey_bytes(&self.agg_pubkey)
+    }
+
+    pub fn agg_pubkey_base58(&self) -> String {
+        pubkey_base58(&self.agg_pubkey)
+    }
+}
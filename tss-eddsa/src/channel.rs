@@ -0,0 +1,276 @@
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use curv::elliptic::curves::{Ed25519, Point, Scalar};
+use hkdf::Hkdf;
+use multi_party_eddsa::protocols::Signature;
+use rand::Rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    echo::EchoMsg,
+    error::TssError,
+    frost::{FrostMsg1, FrostMsg2},
+    keygen::{KeyGenComplaintMsg1, KeyGenComplaintMsg2, KeyGenMsg1, KeyGenMsg2, KeyGenMsg3},
+    recover::{RecoverMsg1, RecoverMsg2},
+    refresh::{RefreshMsg1, RefreshMsg2},
+    reshare::{ReshareMsg1, ReshareMsg2},
+    sign::{SignMsg1, SignMsg2, SignMsg3, SignMsg4},
+    TssResult,
+};
+
+// Optional transport layer for relays that must not see the protocol. P2P
+// messages (`KeyGenMsg3`, `SignMsg3`, ...) are sealed with ChaCha20-Poly1305
+// under a key derived from static ECDH between the two identity keys, so only
+// the receiver can read them and only the sender could have written them.
+// Broadcast messages are signed with the sender's identity key. Identity
+// public keys have to be exchanged and pinned out of band. Every message is
+// bound to a `label` naming the session and round, so it cannot be replayed
+// into another one. The `sender` (and `receiver`) a message names must match
+// the authenticated ones, or a party could pass off its own message as
+// someone else's.
+
+const P2P_KDF_SALT: &[u8] = b"tss-eddsa p2p";
+const BROADCAST_DOMAIN: &[u8] = b"tss-eddsa broadcast";
+
+/// A protocol message naming its sender.
+pub trait SenderMsg {
+    fn sender(&self) -> u16;
+}
+
+/// A point to point protocol message naming its sender and receiver.
+pub trait P2pMsg: SenderMsg {
+    fn receiver(&self) -> u16;
+}
+
+macro_rules! impl_sender_msg {
+    ($($msg:ty),*) => {
+        $(impl SenderMsg for $msg {
+            fn sender(&self) -> u16 {
+                self.sender
+            }
+        })*
+    };
+}
+
+macro_rules! impl_p2p_msg {
+    ($($msg:ty),*) => {
+        impl_sender_msg!($($msg),*);
+        $(impl P2pMsg for $msg {
+            fn receiver(&self) -> u16 {
+                self.receiver
+            }
+        })*
+    };
+}
+
+impl_sender_msg!(
+    EchoMsg,
+    FrostMsg1,
+    FrostMsg2,
+    KeyGenMsg1,
+    KeyGenMsg2,
+    KeyGenComplaintMsg1,
+    KeyGenComplaintMsg2,
+    RefreshMsg1,
+    ReshareMsg1,
+    SignMsg1,
+    SignMsg2,
+    SignMsg4
+);
+impl_p2p_msg!(
+    KeyGenMsg3,
+    RecoverMsg1,
+    RecoverMsg2,
+    RefreshMsg2,
+    ReshareMsg2,
+    SignMsg3
+);
+
+/// Long-term identity key of a party.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IdentityKey {
+    pub secret: Scalar<Ed25519>,
+    pub public: Point<Ed25519>,
+}
+
+impl IdentityKey {
+    pub fn create() -> Self {
+        let secret = Scalar::random();
+        let public = Point::generator() * &secret;
+        Self { secret, public }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SealedMsg {
+    pub sender: u16,
+    pub receiver: u16,
+    pub nonce: [u8; 12],
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SignedMsg {
+    pub sender: u16,
+    pub payload: Vec<u8>,
+    pub signature: Signature,
+}
+
+/// Rejects the identity and small-order points, which would make the ECDH
+/// secret predictable or let anyone forge signatures.
+fn check_identity_key(pk: &Point<Ed25519>) -> TssResult<()> {
+    if (pk * Scalar::<Ed25519>::from(8u16)).is_zero() {
+        return Err(TssError::SpecificError("invalid identity key".into()));
+    }
+    Ok(())
+}
+
+/// Key for messages from `sender` to `receiver`; each direction gets its own.
+fn p2p_key(
+    identity: &IdentityKey,
+    peer_pk: &Point<Ed25519>,
+    sender: u16,
+    receiver: u16,
+) -> TssResult<ChaCha20Poly1305> {
+    check_identity_key(peer_pk)?;
+    let shared = peer_pk * &identity.secret;
+    let hkdf = Hkdf::<Sha256>::new(Some(P2P_KDF_SALT), &shared.to_bytes(true));
+    let mut key = [0u8; 32];
+    hkdf.expand(&p2p_header(sender, receiver), &mut key)
+        .map_err(|_| TssError::SpecificError("p2p key derivation failed".into()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn p2p_header(sender: u16, receiver: u16) -> [u8; 4] {
+    let mut header = [0u8; 4];
+    header[..2].copy_from_slice(&sender.to_be_bytes());
+    header[2..].copy_from_slice(&receiver.to_be_bytes());
+    header
+}
+
+fn p2p_aad(sender: u16, receiver: u16, label: &[u8]) -> Vec<u8> {
+    [
+        &p2p_header(sender, receiver)[..],
+        &(label.len() as u64).to_be_bytes(),
+        label,
+    ]
+    .concat()
+}
+
+fn broadcast_message(sender: u16, label: &[u8], payload: &[u8]) -> Vec<u8> {
+    [
+        BROADCAST_DOMAIN,
+        &(label.len() as u64).to_be_bytes(),
+        label,
+        &sender.to_be_bytes(),
+        payload,
+    ]
+    .concat()
+}
+
+/// Encrypts `msg` from `sender` to `receiver`, whose identity key is `receiver_pk`.
+pub fn seal<T: Serialize>(
+    identity: &IdentityKey,
+    sender: u16,
+    receiver: u16,
+    receiver_pk: &Point<Ed25519>,
+    label: &[u8],
+    msg: &T,
+) -> TssResult<SealedMsg> {
+    let nonce: [u8; 12] = rand::thread_rng().gen();
+    let plaintext = serde_json::to_vec(msg)?;
+    let ciphertext = p2p_key(identity, receiver_pk, sender, receiver)?
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &p2p_aad(sender, receiver, label),
+            },
+        )
+        .map_err(|_| TssError::SpecificError("p2p encryption failed".into()))?;
+
+    Ok(SealedMsg {
+        sender,
+        receiver,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Decrypts a message addressed to `receiver` from the holder of `sender_pk`,
+/// sealed under the same `label`.
+pub fn open<T: DeserializeOwned + P2pMsg>(
+    identity: &IdentityKey,
+    receiver: u16,
+    sender_pk: &Point<Ed25519>,
+    label: &[u8],
+    sealed: &SealedMsg,
+) -> TssResult<T> {
+    if sealed.receiver != receiver {
+        return Err(TssError::WrongReceiver(sealed.sender));
+    }
+    let plaintext = p2p_key(identity, sender_pk, sealed.sender, sealed.receiver)?
+        .decrypt(
+            Nonce::from_slice(&sealed.nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad: &p2p_aad(sealed.sender, sealed.receiver, label),
+            },
+        )
+        .map_err(|_| TssError::AuthenticationFailed(sealed.sender))?;
+
+    let msg: T = serde_json::from_slice(&plaintext)?;
+    if msg.sender() != sealed.sender || msg.receiver() != sealed.receiver {
+        return Err(TssError::AuthenticationFailed(sealed.sender));
+    }
+    Ok(msg)
+}
+
+/// Signs a broadcast `msg` from `sender` with its identity key.
+pub fn sign_broadcast<T: Serialize>(
+    identity: &IdentityKey,
+    sender: u16,
+    label: &[u8],
+    msg: &T,
+) -> TssResult<SignedMsg> {
+    let payload = serde_json::to_vec(msg)?;
+    let message = broadcast_message(sender, label, &payload);
+
+    let r = Scalar::<Ed25519>::random();
+    let nonce_pk = Point::generator() * &r;
+    let k = Signature::k(&nonce_pk, &identity.public, &message);
+    let signature = Signature {
+        R: nonce_pk,
+        s: r + k * &identity.secret,
+    };
+
+    Ok(SignedMsg {
+        sender,
+        payload,
+        signature,
+    })
+}
+
+/// Checks the signature of `sender_pk` on a broadcast message signed under
+/// the same `label` and returns it.
+pub fn open_broadcast<T: DeserializeOwned + SenderMsg>(
+    sender_pk: &Point<Ed25519>,
+    label: &[u8],
+    signed: &SignedMsg,
+) -> TssResult<T> {
+    check_identity_key(sender_pk)?;
+    let message = broadcast_message(signed.sender, label, &signed.payload);
+    signed
+        .signature
+        .verify(&message, sender_pk)
+        .map_err(|_| TssError::AuthenticationFailed(signed.sender))?;
+
+    let msg: T = serde_json::from_slice(&signed.payload)?;
+    if msg.sender() != signed.sender {
+        return Err(TssError::AuthenticationFailed(signed.sender));
+    }
+    Ok(msg)
+}
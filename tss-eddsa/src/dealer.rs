@@ -0,0 +1,67 @@
+use curv::{
+    arithmetic::traits::*,
+    cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS,
+    elliptic::curves::{Ed25519, Point, Scalar},
+    BigInt,
+};
+use multi_party_eddsa::protocols::thresholdsig::{Keys, Parameters};
+use sha2::{Digest, Sha512};
+
+use crate::{
+    error::TssError,
+    keygen::{shared_keys, SecretShare, SignKey},
+    validate::check_params,
+    TssResult,
+};
+
+// Trusted dealer: whoever runs this sees the whole secret key, so it should
+// only be used to migrate an existing key (on a machine that already holds it)
+// or in tests. The keys it returns keep the original public key, and each one
+// must be handed to its party over a secure channel and then erased here.
+
+/// Splits the key derived from an RFC 8032 `seed` into `n` shares with
+/// threshold `t`.
+pub fn dealer_split_seed(seed: &[u8; 32], t: u16, n: u16) -> TssResult<Vec<SignKey>> {
+    let mut expanded = [0u8; 64];
+    expanded.copy_from_slice(&Sha512::digest(seed));
+    expanded[0] &= 248;
+    expanded[31] &= 127;
+    expanded[31] |= 64;
+    dealer_split_expanded(&expanded, t, n)
+}
+
+/// Splits an expanded secret key (`scalar || prefix`, as stored by most
+/// wallets) into `n` shares with threshold `t`. The prefix is not needed,
+/// since threshold signing makes its own nonces.
+pub fn dealer_split_expanded(expanded: &[u8; 64], t: u16, n: u16) -> TssResult<Vec<SignKey>> {
+    check_params(t, n, 1)?;
+
+    let mut scalar = expanded[..32].to_vec();
+    scalar.reverse();
+    let secret = Scalar::<Ed25519>::from_bigint(&BigInt::from_bytes(&scalar));
+    let agg_pubkey = Point::generator() * &secret;
+
+    let (vss_scheme, secret_shares) = VerifiableSS::share(t, n, &secret);
+    let mut sign_keys = vec![];
+    for (i, x_i) in secret_shares.iter().enumerate() {
+        let party_index = i as u16 + 1;
+        vss_scheme
+            .validate_share(x_i, party_index)
+            .map_err(|_| TssError::SpecificError("dealt share is inconsistent".into()))?;
+
+        let keypair = Keys::phase1_create(party_index);
+        sign_keys.push(SignKey {
+            params: Parameters {
+                threshold: t,
+                share_count: n,
+            },
+            agg_pubkey: agg_pubkey.clone(),
+            vss_schemes: vec![vss_scheme.clone()],
+            secret_share: SecretShare {
+                shared_keys: shared_keys(&keypair, &agg_pubkey, x_i.clone())?,
+                keypair,
+            },
+        });
+    }
+    Ok(sign_keys)
+}
@@ -0,0 +1,120 @@
+use curv::{
+    arithmetic::traits::*,
+    elliptic::curves::{Ed25519, Point, Scalar},
+    BigInt,
+};
+use hmac::{Hmac, Mac, NewMac};
+use multi_party_eddsa::protocols::thresholdsig::Keys;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    error::TssError,
+    keygen::{shared_keys, sum_vss_schemes, SecretShare, SignKey},
+    TssResult,
+};
+
+// Non-hardened derivation by additive tweak. At each step
+// `I = HMAC-SHA512(chain_code, A || index)`, the left half reduced mod `l` is
+// the tweak `t` and the right half is the child chain code. The child public
+// key is `A + t * G`, so it only needs public data, and every party adds the
+// same `t` to its share, which shifts the whole sharing by `t`.
+
+const HARDENED: u32 = 1 << 31;
+
+fn scalar_from_le(bytes: &[u8]) -> Scalar<Ed25519> {
+    let mut bytes = bytes.to_vec();
+    bytes.reverse();
+    Scalar::from_bigint(&BigInt::from_bytes(&bytes))
+}
+
+/// Tweak and child chain code for one step of the path.
+fn derive_step(
+    pubkey: &Point<Ed25519>,
+    chain_code: &[u8; 32],
+    index: u32,
+) -> TssResult<(Scalar<Ed25519>, [u8; 32])> {
+    if index >= HARDENED {
+        return Err(TssError::InvalidParameters);
+    }
+    let mut mac = Hmac::<Sha512>::new_from_slice(chain_code)
+        .map_err(|_| TssError::SpecificError("invalid chain code".into()))?;
+    mac.update(&pubkey.to_bytes(true));
+    mac.update(&index.to_be_bytes());
+    let out = mac.finalize().into_bytes();
+
+    let mut child_chain_code = [0u8; 32];
+    child_chain_code.copy_from_slice(&out[32..]);
+    Ok((scalar_from_le(&out[..32]), child_chain_code))
+}
+
+/// Sum of the tweaks along `path`, and the final chain code.
+fn derive_tweak(
+    pubkey: &Point<Ed25519>,
+    chain_code: &[u8; 32],
+    path: &[u32],
+) -> TssResult<(Scalar<Ed25519>, [u8; 32])> {
+    let mut pubkey = pubkey.clone();
+    let mut chain_code = *chain_code;
+    let mut tweak = Scalar::<Ed25519>::zero();
+    for &index in path {
+        let (step, child_chain_code) = derive_step(&pubkey, &chain_code, index)?;
+        pubkey = pubkey + Point::generator() * &step;
+        chain_code = child_chain_code;
+        tweak = tweak + step;
+    }
+    Ok((tweak, chain_code))
+}
+
+/// Child public key and chain code at `path`.
+pub fn derive_pubkey(
+    agg_pubkey: &Point<Ed25519>,
+    chain_code: &[u8; 32],
+    path: &[u32],
+) -> TssResult<(Point<Ed25519>, [u8; 32])> {
+    let (tweak, chain_code) = derive_tweak(agg_pubkey, chain_code, path)?;
+    Ok((agg_pubkey + Point::generator() * tweak, chain_code))
+}
+
+/// Derives this party's share of the child key at `path`, and the child chain
+/// code. The nonce prefix is re-derived as well, so deterministic nonces never
+/// repeat between a parent and a child key.
+pub fn derive_sign_key(
+    sign_key: &SignKey,
+    chain_code: &[u8; 32],
+    path: &[u32],
+) -> TssResult<(SignKey, [u8; 32])> {
+    let (tweak, chain_code) = derive_tweak(&sign_key.agg_pubkey, chain_code, path)?;
+    let tweak_pk = Point::generator() * &tweak;
+    let agg_pubkey = &sign_key.agg_pubkey + &tweak_pk;
+
+    let mut vss_scheme = sum_vss_schemes(&sign_key.vss_schemes)?;
+    vss_scheme.commitments[0] = &vss_scheme.commitments[0] + &tweak_pk;
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let x_i = &sign_key.secret_share.shared_keys.x_i + &tweak;
+    vss_scheme
+        .validate_share(&x_i, party_index)
+        .map_err(|_| TssError::SpecificError("derived share is inconsistent".into()))?;
+
+    let mut keypair = serde_json::to_value(&sign_key.secret_share.keypair)?;
+    let prefix: Scalar<Ed25519> =
+        serde_json::from_value(keypair["keypair"]["expanded_private_key"]["prefix"].take())?;
+    let hash = Sha512::new()
+        .chain(b"tss-eddsa derive prefix")
+        .chain(&*prefix.to_bytes())
+        .chain(&*tweak.to_bytes())
+        .finalize();
+    keypair["keypair"]["expanded_private_key"]["prefix"] =
+        serde_json::to_value(scalar_from_le(&hash))?;
+    let keypair: Keys = serde_json::from_value(keypair)?;
+
+    let child = SignKey {
+        params: sign_key.params.clone(),
+        agg_pubkey: agg_pubkey.clone(),
+        vss_schemes: vec![vss_scheme],
+        secret_share: SecretShare {
+            shared_keys: shared_keys(&keypair, &agg_pubkey, x_i)?,
+            keypair,
+        },
+    };
+    Ok((child, chain_code))
+}
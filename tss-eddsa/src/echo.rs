@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::TssError,
+    keygen::{KeyGenComplaintMsg1, KeyGenComplaintMsg2, KeyGenMsg1, KeyGenMsg2},
+    refresh::RefreshMsg1,
+    reshare::ReshareMsg1,
+    sign::{SignMsg1, SignMsg2},
+    validate::check_senders,
+    TssResult,
+};
+
+// Optional echo step after a broadcast round. Every party hashes each message
+// it received and sends the digests to all others. If they do not all agree,
+// some sender (or the relay) showed different messages to different parties,
+// and the protocol must stop before any shares are distributed.
+//
+// `EchoMsg::sender` is not authenticated here: echoes must travel over an
+// authenticated channel, e.g. `channel::sign_broadcast`, or the relay can
+// forge an echo for every party and hide the equivocation.
+
+const ECHO_DOMAIN: &[u8] = b"tss-eddsa echo";
+
+pub trait BroadcastMsg: Serialize {
+    const ROUND: &'static str;
+
+    fn sender(&self) -> u16;
+}
+
+impl BroadcastMsg for KeyGenMsg1 {
+    const ROUND: &'static str = "keygen1";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for KeyGenMsg2 {
+    const ROUND: &'static str = "keygen2";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for KeyGenComplaintMsg1 {
+    const ROUND: &'static str = "complaint1";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for KeyGenComplaintMsg2 {
+    const ROUND: &'static str = "complaint2";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for RefreshMsg1 {
+    const ROUND: &'static str = "refresh1";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for ReshareMsg1 {
+    const ROUND: &'static str = "reshare1";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for SignMsg1 {
+    const ROUND: &'static str = "sign1";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl BroadcastMsg for SignMsg2 {
+    const ROUND: &'static str = "sign2";
+
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EchoMsg {
+    pub sender: u16,
+    pub round: String,
+    pub digests: Vec<(u16, [u8; 32])>,
+}
+
+/// Digest of every message in the round, sorted by sender.
+fn transcript<M: BroadcastMsg>(msgs: &[M]) -> TssResult<Vec<(u16, [u8; 32])>> {
+    let mut digests = vec![];
+    for msg in msgs {
+        let hash = Sha256::new()
+            .chain(ECHO_DOMAIN)
+            .chain(M::ROUND.as_bytes())
+            .chain(msg.sender().to_be_bytes())
+            .chain(serde_json::to_vec(msg)?)
+            .finalize();
+        digests.push((msg.sender(), hash.into()));
+    }
+    digests.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(w) = digests.windows(2).find(|w| w[0].0 == w[1].0) {
+        return Err(TssError::DuplicateSender(w[0].0));
+    }
+    Ok(digests)
+}
+
+/// Echoes the broadcast messages `msgs` this party received in a round.
+pub fn echo_phase1<M: BroadcastMsg>(party_index: u16, msgs: &[M]) -> TssResult<EchoMsg> {
+    Ok(EchoMsg {
+        sender: party_index,
+        round: M::ROUND.into(),
+        digests: transcript(msgs)?,
+    })
+}
+
+/// Checks every party's echo (including our own) against `msgs`. `parties`
+/// is the set of parties taking part in the round, as known to the caller,
+/// and each of them must have sent exactly one message and one echo.
+pub fn echo_phase2<M: BroadcastMsg>(
+    parties: &[u16],
+    msgs: &[M],
+    echoes: &[EchoMsg],
+) -> TssResult<()> {
+    echo_phase2_from(parties, parties, msgs, echoes)
+}
+
+/// Like `echo_phase2`, for a round whose messages come from `senders` and
+/// are echoed among `receivers`, e.g. an old committee dealing to a new one,
+/// or the answers of accused dealers in the keygen complaint round.
+pub fn echo_phase2_from<M: BroadcastMsg>(
+    senders: &[u16],
+    receivers: &[u16],
+    msgs: &[M],
+    echoes: &[EchoMsg],
+) -> TssResult<()> {
+    let digests = transcript(msgs)?;
+    check_senders(digests.iter().map(|(sender, _)| *sender), senders)?;
+    check_senders(echoes.iter().map(|echo| echo.sender), receivers)?;
+
+    for echo in echoes {
+        if echo.round != M::ROUND {
+            return Err(TssError::SpecificError(format!(
+                "echo from party {} is for round {}",
+                echo.sender, echo.round
+            )));
+        }
+        let inconsistent = digests
+            .iter()
+            .find(|digest| !echo.digests.contains(digest))
+            .or_else(|| echo.digests.iter().find(|digest| !digests.contains(digest)));
+        if let Some(&(sender, _)) = inconsistent {
+            return Err(TssError::Equivocation {
+                sender,
+                echoed_by: echo.sender,
+            });
+        }
+    }
+    Ok(())
+}
@@ -11,6 +11,36 @@ pub enum TssError {
     #[error("inputs length unmatch")]
     InputsLengthUnmatch,
 
+    #[error("invalid parameters")]
+    InvalidParameters,
+
+    #[error("message from unknown party {0}")]
+    UnknownSender(u16),
+
+    #[error("duplicate message from party {0}")]
+    DuplicateSender(u16),
+
+    #[error("message from party {0} is addressed to another party")]
+    WrongReceiver(u16),
+
+    #[error("message from party {0} belongs to another session")]
+    WrongSession(u16),
+
+    #[error("message from party {0} failed authentication")]
+    AuthenticationFailed(u16),
+
+    #[error("party {sender} sent inconsistent broadcasts, reported by party {echoed_by}")]
+    Equivocation { sender: u16, echoed_by: u16 },
+
+    #[error("invalid keygen shares from parties {0:?}")]
+    InvalidKeyGenShares(Vec<u16>),
+
+    #[error("parties {0:?} disqualified from keygen")]
+    KeyGenDisqualified(Vec<u16>),
+
+    #[error("invalid partial signatures from parties {0:?}")]
+    InvalidPartialSignatures(Vec<u16>),
+
     #[error(transparent)]
     SerdeJsonError(#[from] serde_json::error::Error),
 
@@ -0,0 +1,276 @@
+use curv::{
+    arithmetic::traits::*,
+    cryptographic_primitives::secret_sharing::feldman_vss::{ShamirSecretSharing, VerifiableSS},
+    elliptic::curves::{Ed25519, Point, Scalar},
+    BigInt,
+};
+use multi_party_eddsa::protocols::Signature;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use crate::{
+    error::TssError,
+    keygen::{sum_vss_schemes, SignKey},
+    validate::{check_parties, check_senders},
+    TssResult,
+};
+
+// FROST(Ed25519, SHA-512) from RFC 9591 over the existing Shamir shares, with
+// party index `i` as the FROST identifier. `frost_phase1` commits to a batch
+// of nonces: with a batch of one it is the first of two signing rounds, with a
+// larger batch it is a preprocessing step and signing needs only
+// `frost_phase2`. Each nonce is deleted from the context when it is used, so
+// the updated context must be persisted before the share is sent.
+
+const CONTEXT_STRING: &[u8] = b"FROST-ED25519-SHA512-v1";
+
+// secret structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrostNonce {
+    pub hiding: Scalar<Ed25519>,
+    pub binding: Scalar<Ed25519>,
+    pub commitment: FrostCommitment,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrostContext1 {
+    pub nonces: Vec<FrostNonce>,
+}
+
+// public structures
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrostCommitment {
+    pub sender: u16,
+    pub id: u64,
+    pub hiding: Point<Ed25519>,
+    pub binding: Point<Ed25519>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrostMsg1 {
+    pub sender: u16,
+    pub commitments: Vec<FrostCommitment>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrostMsg2 {
+    pub sender: u16,
+    pub sig_share: Scalar<Ed25519>,
+}
+
+fn hash(tag: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(CONTEXT_STRING);
+    hasher.update(tag);
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Reduces a little-endian 64-byte hash modulo the group order.
+fn hash_to_scalar(tag: &[u8], parts: &[&[u8]]) -> Scalar<Ed25519> {
+    let mut hash = hash(tag, parts);
+    hash.reverse();
+    Scalar::from_bigint(&BigInt::from_bytes(&hash))
+}
+
+fn identifier(party_index: u16) -> Scalar<Ed25519> {
+    Scalar::from(party_index)
+}
+
+fn nonce_generate(secret: &Scalar<Ed25519>) -> Scalar<Ed25519> {
+    let random_bytes: [u8; 32] = rand::thread_rng().gen();
+    hash_to_scalar(b"nonce", &[&random_bytes, &secret.to_bytes()])
+}
+
+/// Binding factor of every signer, in the order of `commitments`.
+fn binding_factors(
+    agg_pubkey: &Point<Ed25519>,
+    commitments: &[FrostCommitment],
+    message: &[u8],
+) -> Vec<Scalar<Ed25519>> {
+    let mut encoded = vec![];
+    for commitment in commitments {
+        encoded.extend_from_slice(&identifier(commitment.sender).to_bytes());
+        encoded.extend_from_slice(&commitment.hiding.to_bytes(true));
+        encoded.extend_from_slice(&commitment.binding.to_bytes(true));
+    }
+    let prefix = [
+        agg_pubkey.to_bytes(true).to_vec(),
+        hash(b"msg", &[message]),
+        hash(b"com", &[&encoded]),
+    ]
+    .concat();
+
+    commitments
+        .iter()
+        .map(|commitment| {
+            hash_to_scalar(
+                b"rho",
+                &[&prefix, &identifier(commitment.sender).to_bytes()],
+            )
+        })
+        .collect()
+}
+
+fn group_commitment(
+    commitments: &[FrostCommitment],
+    binding_factors: &[Scalar<Ed25519>],
+) -> Point<Ed25519> {
+    commitments
+        .iter()
+        .zip(binding_factors)
+        .fold(Point::zero(), |acc, (commitment, rho)| {
+            acc + &commitment.hiding + &commitment.binding * rho
+        })
+}
+
+/// Lagrange coefficient of `party_index` at zero over the signers.
+fn lagrange(sign_key: &SignKey, party_index: u16, signers: &[u16]) -> Scalar<Ed25519> {
+    let params = ShamirSecretSharing {
+        threshold: sign_key.params.threshold,
+        share_count: sign_key.params.share_count,
+    };
+    let signers_indexs: Vec<_> = signers.iter().map(|a| a - 1).collect();
+    VerifiableSS::<Ed25519>::map_share_to_new_params(&params, party_index - 1, &signers_indexs)
+}
+
+/// Sorts the signing package and checks that it has one commitment per signer
+/// and no identity commitments.
+fn signers(commitments: &mut [FrostCommitment]) -> TssResult<Vec<u16>> {
+    commitments.sort_by(|a, b| a.sender.cmp(&b.sender));
+    if let Some(c) = commitments
+        .iter()
+        .find(|c| c.hiding.is_zero() || c.binding.is_zero())
+    {
+        return Err(TssError::SpecificError(format!(
+            "identity commitment from party {}",
+            c.sender
+        )));
+    }
+    let signers: Vec<_> = commitments.iter().map(|c| c.sender).collect();
+    if let Some(w) = signers.windows(2).find(|w| w[0] == w[1]) {
+        return Err(TssError::DuplicateSender(w[0]));
+    }
+    Ok(signers)
+}
+
+/// Commits to `count` fresh nonces.
+pub fn frost_phase1(sign_key: &SignKey, count: usize) -> TssResult<(FrostContext1, FrostMsg1)> {
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let x_i = &sign_key.secret_share.shared_keys.x_i;
+
+    let mut nonces = vec![];
+    for _ in 0..count {
+        let hiding = nonce_generate(x_i);
+        let binding = nonce_generate(x_i);
+        let commitment = FrostCommitment {
+            sender: party_index,
+            id: rand::thread_rng().gen(),
+            hiding: Point::generator() * &hiding,
+            binding: Point::generator() * &binding,
+        };
+        nonces.push(FrostNonce {
+            hiding,
+            binding,
+            commitment,
+        });
+    }
+
+    let msg1 = FrostMsg1 {
+        sender: party_index,
+        commitments: nonces.iter().map(|n| n.commitment.clone()).collect(),
+    };
+    Ok((FrostContext1 { nonces }, msg1))
+}
+
+/// Signs `message` given the package of `commitments`, one from each signer.
+/// Our nonce is removed from `context1` even if signing fails.
+pub fn frost_phase2(
+    sign_key: &SignKey,
+    context1: &mut FrostContext1,
+    message: &[u8],
+    mut commitments: Vec<FrostCommitment>,
+) -> TssResult<FrostMsg2> {
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let signers = signers(&mut commitments)?;
+    check_parties(&sign_key.params, &signers, party_index)?;
+
+    let ours = commitments
+        .iter()
+        .position(|c| c.sender == party_index)
+        .ok_or(TssError::InvalidParameters)?;
+    let nonce = context1
+        .nonces
+        .iter()
+        .position(|n| n.commitment.id == commitments[ours].id)
+        .map(|i| context1.nonces.remove(i))
+        .ok_or_else(|| TssError::SpecificError("unknown or used nonce".into()))?;
+    if nonce.commitment != commitments[ours] {
+        return Err(TssError::SpecificError("nonce commitment mismatch".into()));
+    }
+
+    let rhos = binding_factors(&sign_key.agg_pubkey, &commitments, message);
+    let group_commitment = group_commitment(&commitments, &rhos);
+    let c = Signature::k(&group_commitment, &sign_key.agg_pubkey, message);
+    let lambda = lagrange(sign_key, party_index, &signers);
+
+    let sig_share = nonce.hiding
+        + nonce.binding * &rhos[ours]
+        + lambda * &sign_key.secret_share.shared_keys.x_i * c;
+    Ok(FrostMsg2 {
+        sender: party_index,
+        sig_share,
+    })
+}
+
+/// Verifies every signature share and aggregates them. Can be run by any
+/// holder of `sign_key`.
+pub fn frost_phase3(
+    sign_key: &SignKey,
+    message: &[u8],
+    mut commitments: Vec<FrostCommitment>,
+    mut msgs2: Vec<FrostMsg2>,
+) -> TssResult<Signature> {
+    let signers = signers(&mut commitments)?;
+    msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+    check_senders(msgs2.iter().map(|msg| msg.sender), &signers)?;
+    if signers.len() <= usize::from(sign_key.params.threshold)
+        || signers
+            .iter()
+            .any(|&i| i == 0 || i > sign_key.params.share_count)
+    {
+        return Err(TssError::InvalidParameters);
+    }
+
+    let rhos = binding_factors(&sign_key.agg_pubkey, &commitments, message);
+    let group_commitment = group_commitment(&commitments, &rhos);
+    let c = Signature::k(&group_commitment, &sign_key.agg_pubkey, message);
+    let key_vss = sum_vss_schemes(&sign_key.vss_schemes)?;
+
+    let mut culprits = vec![];
+    for ((msg, commitment), rho) in msgs2.iter().zip(&commitments).zip(&rhos) {
+        let lambda = lagrange(sign_key, msg.sender, &signers);
+        let expected = &commitment.hiding
+            + &commitment.binding * rho
+            + key_vss.get_point_commitment(msg.sender) * (lambda * &c);
+        if Point::<Ed25519>::generator() * &msg.sig_share != expected {
+            culprits.push(msg.sender);
+        }
+    }
+    if !culprits.is_empty() {
+        return Err(TssError::InvalidPartialSignatures(culprits));
+    }
+
+    let s = msgs2
+        .iter()
+        .fold(Scalar::<Ed25519>::zero(), |acc, msg| acc + &msg.sig_share);
+    let sig = Signature {
+        R: group_commitment,
+        s,
+    };
+    sig.verify(message, &sign_key.agg_pubkey)?;
+    Ok(sig)
+}
@@ -7,13 +7,22 @@ use multi_party_eddsa::protocols::thresholdsig::{
     KeyGenBroadcastMessage1, Keys, Parameters, SharedKeys,
 };
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::{error::TssError, TssResult};
+use crate::{
+    error::TssError,
+    validate::{
+        check_known_senders, check_params, check_receivers, check_senders, check_session,
+        session_tag,
+    },
+    TssResult,
+};
 
 // party one structures
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyGenContext1 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub keypair: Keys,
     pub first_msg_blind: BigInt,
 }
@@ -21,6 +30,7 @@ pub struct KeyGenContext1 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyGenMsg1 {
     pub sender: u16,
+    pub session: [u8; 32],
     pub msg: KeyGenBroadcastMessage1,
 }
 
@@ -28,6 +38,7 @@ pub struct KeyGenMsg1 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyGenContext2 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub keypair: Keys,
     pub comm_vec: Vec<KeyGenBroadcastMessage1>,
 }
@@ -36,6 +47,7 @@ pub struct KeyGenContext2 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyGenMsg2 {
     pub sender: u16,
+    pub session: [u8; 32],
     pub pubkey: Point<Ed25519>,
     pub first_msg_blind: BigInt,
 }
@@ -44,6 +56,7 @@ pub struct KeyGenMsg2 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyGenContext3 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub keypair: Keys,
     pub pubkeys_vec: Vec<Point<Ed25519>>,
     pub agg_pubkey: Point<Ed25519>,
@@ -54,10 +67,27 @@ pub struct KeyGenContext3 {
 pub struct KeyGenMsg3 {
     pub sender: u16,
     pub receiver: u16,
+    pub session: [u8; 32],
     pub secret_share: Scalar<Ed25519>,
     pub vss_scheme: VerifiableSS<Ed25519>,
 }
 
+// complaint round structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyGenComplaintMsg1 {
+    pub sender: u16,
+    pub session: [u8; 32],
+    pub accused: Vec<u16>,
+    pub vss_digests: Vec<(u16, [u8; 32])>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyGenComplaintMsg2 {
+    pub sender: u16,
+    pub session: [u8; 32],
+    pub revealed: Vec<KeyGenMsg3>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SecretShare {
     pub keypair: Keys,
@@ -73,7 +103,89 @@ pub struct SignKey {
     pub secret_share: SecretShare,
 }
 
-pub fn keygen_phase1(t: u16, n: u16, party_index: u16) -> TssResult<(KeyGenContext1, KeyGenMsg1)> {
+/// Adds two Feldman commitments to polynomials of the same degree.
+fn add_vss_schemes(
+    a: &VerifiableSS<Ed25519>,
+    b: &VerifiableSS<Ed25519>,
+) -> TssResult<VerifiableSS<Ed25519>> {
+    if a.commitments.len() != b.commitments.len() {
+        return Err(TssError::InputsLengthUnmatch);
+    }
+    Ok(VerifiableSS {
+        parameters: a.parameters.clone(),
+        commitments: a
+            .commitments
+            .iter()
+            .zip(&b.commitments)
+            .map(|(a, b)| a + b)
+            .collect(),
+    })
+}
+
+/// Commitment to the polynomial sharing the joint secret.
+pub(crate) fn sum_vss_schemes(
+    vss_schemes: &[VerifiableSS<Ed25519>],
+) -> TssResult<VerifiableSS<Ed25519>> {
+    let (first, rest) = vss_schemes
+        .split_first()
+        .ok_or(TssError::InputsLengthUnmatch)?;
+    rest.iter()
+        .try_fold(first.clone(), |acc, vss| add_vss_schemes(&acc, vss))
+}
+
+/// Builds the `SharedKeys` that `phase2_verify_vss_construct_keypair` would
+/// return for `keypair`; its fields are private so it goes through serde.
+pub(crate) fn shared_keys(
+    keypair: &Keys,
+    y: &Point<Ed25519>,
+    x_i: Scalar<Ed25519>,
+) -> TssResult<SharedKeys> {
+    let keypair = serde_json::to_value(keypair)?;
+    Ok(serde_json::from_value(serde_json::json!({
+        "y": y,
+        "x_i": x_i,
+        "prefix": keypair["keypair"]["expanded_private_key"]["prefix"],
+    }))?)
+}
+
+/// Digest of a dealer's commitments, compared in the complaint round.
+fn vss_digest(vss: &VerifiableSS<Ed25519>) -> [u8; 32] {
+    let mut hasher = Sha256::new()
+        .chain(b"tss-eddsa vss")
+        .chain(vss.parameters.threshold.to_be_bytes())
+        .chain(vss.parameters.share_count.to_be_bytes());
+    for commitment in &vss.commitments {
+        hasher.update(&*commitment.to_bytes(true));
+    }
+    hasher.finalize().into()
+}
+
+/// Whether `msg` is a share for `receiver` consistent with its sender's
+/// commitments and with the public key the sender opened in phase 3.
+fn valid_msg3(context3: &KeyGenContext3, msg: &KeyGenMsg3, receiver: u16) -> bool {
+    let vss = &msg.vss_scheme;
+    msg.sender != 0
+        && msg.sender <= context3.params.share_count
+        && vss.parameters.threshold == context3.params.threshold
+        && vss.commitments.len() == usize::from(context3.params.threshold) + 1
+        && vss.commitments[0] == context3.pubkeys_vec[usize::from(msg.sender - 1)]
+        && vss.validate_share(&msg.secret_share, receiver).is_ok()
+}
+
+/// `session_id` must be fresh and shared by all parties of this keygen.
+pub fn keygen_phase1(
+    t: u16,
+    n: u16,
+    party_index: u16,
+    session_id: &[u8],
+) -> TssResult<(KeyGenContext1, KeyGenMsg1)> {
+    check_params(t, n, party_index)?;
+    let session = session_tag(
+        b"tss-eddsa keygen",
+        session_id,
+        &[&t.to_be_bytes(), &n.to_be_bytes()],
+    );
+
     let key = Keys::phase1_create(party_index);
 
     let (first_msg, first_msg_blind) = key.phase1_broadcast();
@@ -83,12 +195,14 @@ pub fn keygen_phase1(t: u16, n: u16, party_index: u16) -> TssResult<(KeyGenConte
             threshold: t,
             share_count: n,
         },
+        session,
         keypair: key,
         first_msg_blind,
     };
 
     let msg1 = KeyGenMsg1 {
         sender: party_index,
+        session,
         msg: first_msg,
     };
 
@@ -101,16 +215,25 @@ pub fn keygen_phase2(
 ) -> TssResult<(KeyGenContext2, KeyGenMsg2)> {
     msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
 
+    let parties: Vec<_> = (1..=context1.params.share_count).collect();
+    check_senders(msgs1.iter().map(|msg| msg.sender), &parties)?;
+    check_session(
+        msgs1.iter().map(|msg| (msg.sender, msg.session)),
+        &context1.session,
+    )?;
+
     let comm_vec = msgs1.into_iter().map(|msg| msg.msg).collect();
 
     let msg2 = KeyGenMsg2 {
         sender: context1.keypair.party_index,
+        session: context1.session,
         pubkey: context1.keypair.keypair.public_key.clone(),
         first_msg_blind: context1.first_msg_blind,
     };
 
     let context2 = KeyGenContext2 {
         params: context1.params,
+        session: context1.session,
         keypair: context1.keypair,
         comm_vec,
     };
@@ -124,6 +247,13 @@ pub fn keygen_phase3(
 ) -> TssResult<(KeyGenContext3, Vec<KeyGenMsg3>)> {
     msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
 
+    let parties: Vec<_> = (1..=context2.params.share_count).collect();
+    check_senders(msgs2.iter().map(|msg| msg.sender), &parties)?;
+    check_session(
+        msgs2.iter().map(|msg| (msg.sender, msg.session)),
+        &context2.session,
+    )?;
+
     let (blind_vec, pubkeys_vec): (Vec<_>, Vec<_>) = msgs2
         .into_iter()
         .map(|msg2| (msg2.first_msg_blind, msg2.pubkey))
@@ -140,8 +270,6 @@ pub fn keygen_phase3(
         pubkeys_vec[1..].iter().fold(first_key, |acc, p| acc + p)
     };
 
-    let parties: Vec<_> = (1..=context2.params.share_count).collect();
-
     let (vss_scheme, secret_shares) = context2.keypair.phase1_verify_com_phase2_distribute(
         &context2.params,
         &blind_vec,
@@ -154,6 +282,7 @@ pub fn keygen_phase3(
         msg3.push(KeyGenMsg3 {
             sender: context2.keypair.party_index,
             receiver: i as u16 + 1,
+            session: context2.session,
             secret_share: secret_shares[i].clone(),
             vss_scheme: vss_scheme.clone(),
         });
@@ -161,6 +290,7 @@ pub fn keygen_phase3(
 
     let context3 = KeyGenContext3 {
         params: context2.params,
+        session: context2.session,
         keypair: context2.keypair,
         pubkeys_vec,
         agg_pubkey,
@@ -168,8 +298,23 @@ pub fn keygen_phase3(
     Ok((context3, msg3))
 }
 
+/// Checks that `msgs3` hold one message from every party, addressed to us.
+fn check_msgs3(context3: &KeyGenContext3, msgs3: &[KeyGenMsg3]) -> TssResult<()> {
+    let parties: Vec<_> = (1..=context3.params.share_count).collect();
+    check_senders(msgs3.iter().map(|msg| msg.sender), &parties)?;
+    check_receivers(
+        msgs3.iter().map(|msg| (msg.sender, msg.receiver)),
+        context3.keypair.party_index,
+    )?;
+    check_session(
+        msgs3.iter().map(|msg| (msg.sender, msg.session)),
+        &context3.session,
+    )
+}
+
 pub fn keygen_phase4(context3: KeyGenContext3, mut msgs3: Vec<KeyGenMsg3>) -> TssResult<SignKey> {
     msgs3.sort_by(|a, b| a.sender.cmp(&b.sender));
+    check_msgs3(&context3, &msgs3)?;
 
     let secret_shares_vec = msgs3
         .iter()
@@ -187,6 +332,16 @@ pub fn keygen_phase4(context3: KeyGenContext3, mut msgs3: Vec<KeyGenMsg3>) -> Ts
         return Err(TssError::InputsLengthUnmatch);
     }
 
+    let party_index = context3.keypair.party_index;
+    let culprits: Vec<_> = msgs3
+        .iter()
+        .filter(|msg| !valid_msg3(&context3, msg, party_index))
+        .map(|msg| msg.sender)
+        .collect();
+    if !culprits.is_empty() {
+        return Err(TssError::InvalidKeyGenShares(culprits));
+    }
+
     let shared_keys = context3.keypair.phase2_verify_vss_construct_keypair(
         &context3.params,
         &context3.pubkeys_vec,
@@ -205,3 +360,159 @@ pub fn keygen_phase4(context3: KeyGenContext3, mut msgs3: Vec<KeyGenMsg3>) -> Ts
         },
     })
 }
+
+// Complaint round, run instead of `keygen_phase4`. Every party broadcasts the
+// dealers it accuses (possibly none) along with a digest of the commitments it
+// received from every dealer, and each accused dealer reveals the shares it
+// sent to its accusers. A dealer is disqualified if the digests reported for
+// it differ, if it does not answer, or if a revealed share is missing or does
+// not match the agreed commitments.
+//
+// The `vss_scheme` in `KeyGenMsg3` travels point to point, so a dealer can
+// show each party different commitments that match its share; `keygen_phase4`
+// alone cannot notice. Complaint messages must be broadcast reliably, e.g.
+// with `echo_phase2` for the complaints and `echo_phase2_from` for the answers
+// that arrived, then all parties see the same complaints and answers and
+// agree on the disqualified set. Running the round even when nobody got
+// `InvalidKeyGenShares` is what makes parties agree on the commitments.
+
+pub fn keygen_complaint_phase1(
+    context3: &KeyGenContext3,
+    msgs3: &[KeyGenMsg3],
+) -> TssResult<KeyGenComplaintMsg1> {
+    check_msgs3(context3, msgs3)?;
+
+    let party_index = context3.keypair.party_index;
+    let mut accused: Vec<_> = msgs3
+        .iter()
+        .filter(|msg| !valid_msg3(context3, msg, party_index))
+        .map(|msg| msg.sender)
+        .collect();
+    accused.sort();
+    accused.dedup();
+    let mut vss_digests: Vec<_> = msgs3
+        .iter()
+        .map(|msg| (msg.sender, vss_digest(&msg.vss_scheme)))
+        .collect();
+    vss_digests.sort_by(|a, b| a.0.cmp(&b.0));
+
+    Ok(KeyGenComplaintMsg1 {
+        sender: party_index,
+        session: context3.session,
+        accused,
+        vss_digests,
+    })
+}
+
+/// `sent_msgs3` are the messages this party dealt in `keygen_phase3`.
+pub fn keygen_complaint_phase2(
+    context3: &KeyGenContext3,
+    sent_msgs3: &[KeyGenMsg3],
+    msgs1: &[KeyGenComplaintMsg1],
+) -> TssResult<KeyGenComplaintMsg2> {
+    let parties: Vec<_> = (1..=context3.params.share_count).collect();
+    check_senders(msgs1.iter().map(|msg| msg.sender), &parties)?;
+    check_session(
+        msgs1.iter().map(|msg| (msg.sender, msg.session)),
+        &context3.session,
+    )?;
+
+    let party_index = context3.keypair.party_index;
+    let revealed = msgs1
+        .iter()
+        .filter(|msg| msg.accused.contains(&party_index))
+        .filter_map(|msg| sent_msgs3.iter().find(|sent| sent.receiver == msg.sender))
+        .cloned()
+        .collect();
+
+    Ok(KeyGenComplaintMsg2 {
+        sender: party_index,
+        session: context3.session,
+        revealed,
+    })
+}
+
+/// Fails with `KeyGenDisqualified` if any dealer is disqualified, otherwise
+/// finishes keygen, taking revealed shares in place of the ones complained of.
+/// `msgs2` may miss the answers of dealers that went silent.
+pub fn keygen_complaint_phase3(
+    context3: KeyGenContext3,
+    mut msgs3: Vec<KeyGenMsg3>,
+    mut msgs1: Vec<KeyGenComplaintMsg1>,
+    mut msgs2: Vec<KeyGenComplaintMsg2>,
+) -> TssResult<SignKey> {
+    msgs3.sort_by(|a, b| a.sender.cmp(&b.sender));
+    msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
+    msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+
+    let n = context3.params.share_count;
+    let parties: Vec<_> = (1..=n).collect();
+    check_msgs3(&context3, &msgs3)?;
+    check_senders(msgs1.iter().map(|msg| msg.sender), &parties)?;
+    check_known_senders(msgs2.iter().map(|msg| msg.sender), &parties)?;
+    check_session(
+        msgs1.iter().map(|msg| (msg.sender, msg.session)),
+        &context3.session,
+    )?;
+    check_session(
+        msgs2.iter().map(|msg| (msg.sender, msg.session)),
+        &context3.session,
+    )?;
+    if let Some(complaint) = msgs1.iter().find(|msg| {
+        msg.vss_digests.len() != parties.len()
+            || msg.vss_digests.iter().zip(&parties).any(|(d, &i)| d.0 != i)
+    }) {
+        return Err(TssError::SpecificError(format!(
+            "invalid complaint from party {}",
+            complaint.sender
+        )));
+    }
+
+    // dealers reported with different commitments showed them to parties
+    // differently (or a complainer lies), either way keygen cannot finish
+    let mut disqualified: Vec<_> = parties
+        .iter()
+        .filter(|&&dealer| {
+            let index = usize::from(dealer - 1);
+            msgs1
+                .iter()
+                .any(|msg| msg.vss_digests[index] != msgs1[0].vss_digests[index])
+        })
+        .cloned()
+        .collect();
+
+    let party_index = context3.keypair.party_index;
+    for complaint in &msgs1 {
+        for &dealer in complaint.accused.iter().filter(|&&i| i != 0 && i <= n) {
+            let digest = &msgs1[0].vss_digests[usize::from(dealer - 1)].1;
+            let revealed = msgs2
+                .iter()
+                .find(|msg| msg.sender == dealer)
+                .and_then(|msg| {
+                    msg.revealed
+                        .iter()
+                        .find(|msg| msg.receiver == complaint.sender)
+                });
+            match revealed {
+                Some(msg)
+                    if msg.sender == dealer
+                        && msg.session == context3.session
+                        && vss_digest(&msg.vss_scheme) == *digest
+                        && valid_msg3(&context3, msg, complaint.sender) =>
+                {
+                    if complaint.sender == party_index {
+                        msgs3[usize::from(dealer - 1)].secret_share = msg.secret_share.clone();
+                    }
+                }
+                _ => disqualified.push(dealer),
+            }
+        }
+    }
+    disqualified.sort();
+    disqualified.dedup();
+    if !disqualified.is_empty() {
+        return Err(TssError::KeyGenDisqualified(disqualified));
+    }
+
+    keygen_phase4(context3, msgs3)
+}
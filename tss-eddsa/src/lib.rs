@@ -1,9 +1,19 @@
 use error::TssError;
 
+pub mod address;
+pub mod channel;
+pub mod dealer;
+pub mod derive;
+pub mod echo;
 pub mod error;
+pub mod frost;
 pub mod keygen;
+pub mod recover;
+pub mod refresh;
+pub mod reshare;
 pub mod sign;
 mod tests;
+mod validate;
 
 pub type TssResult<T> = Result<T, TssError>;
 
@@ -0,0 +1,203 @@
+use curv::{
+    cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS,
+    elliptic::curves::{Ed25519, Point, Scalar},
+};
+use multi_party_eddsa::protocols::thresholdsig::{Keys, Parameters};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::TssError,
+    keygen::{shared_keys, sum_vss_schemes, SecretShare, SignKey},
+    validate::{check_receivers, check_senders},
+    TssResult,
+};
+
+// The lost share is `f(l) = sum lambda_i(l) * x_i` over `t + 1` helpers. Each
+// helper blinds its term with pairwise masks that cancel in the sum, so the new
+// device learns only `f(l)` and no helper learns anything about it.
+
+// helper structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecoverContext1 {
+    pub helpers: Vec<u16>,
+    pub lost_index: u16,
+    pub blinded_share: Scalar<Ed25519>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecoverMsg1 {
+    pub sender: u16,
+    pub receiver: u16,
+    pub mask: Scalar<Ed25519>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecoverMsg2 {
+    pub sender: u16,
+    pub receiver: u16,
+    pub helpers: Vec<u16>,
+    pub params: Parameters,
+    pub agg_pubkey: Point<Ed25519>,
+    pub vss_schemes: Vec<VerifiableSS<Ed25519>>,
+    pub blinded_share: Scalar<Ed25519>,
+}
+
+/// Lagrange coefficient of `index` for interpolating at `x` over `indexs`.
+fn lagrange_at(index: u16, indexs: &[u16], x: u16) -> TssResult<Scalar<Ed25519>> {
+    let x = Scalar::<Ed25519>::from(x);
+    let xi = Scalar::<Ed25519>::from(index);
+    let mut num = Scalar::<Ed25519>::from(1u16);
+    let mut den = Scalar::<Ed25519>::from(1u16);
+    for &k in indexs.iter().filter(|&&k| k != index) {
+        let xk = Scalar::<Ed25519>::from(k);
+        num = num * (&x - &xk);
+        den = den * (&xi - &xk);
+    }
+    let den_inv = den
+        .invert()
+        .ok_or_else(|| TssError::SpecificError("duplicate party index".into()))?;
+    Ok(num * den_inv)
+}
+
+fn check_helpers(helpers: &[u16], lost_index: u16, params: &Parameters) -> TssResult<()> {
+    let mut sorted = helpers.to_vec();
+    sorted.sort();
+    sorted.dedup();
+    if sorted != helpers
+        || helpers.len() <= usize::from(params.threshold)
+        || helpers[0] == 0
+        || helpers[helpers.len() - 1] > params.share_count
+        || lost_index == 0
+        || lost_index > params.share_count
+        || helpers.contains(&lost_index)
+    {
+        return Err(TssError::InvalidParameters);
+    }
+    Ok(())
+}
+
+/// Run by each helper in `helpers` (at least `t + 1`, not including `lost_index`).
+pub fn recover_phase1(
+    sign_key: &SignKey,
+    mut helpers: Vec<u16>,
+    lost_index: u16,
+) -> TssResult<(RecoverContext1, Vec<RecoverMsg1>)> {
+    helpers.sort();
+    check_helpers(&helpers, lost_index, &sign_key.params)?;
+
+    let party_index = sign_key.secret_share.keypair.party_index;
+    if !helpers.contains(&party_index) {
+        return Err(TssError::InvalidParameters);
+    }
+
+    let lambda = lagrange_at(party_index, &helpers, lost_index)?;
+    let mut blinded_share = lambda * &sign_key.secret_share.shared_keys.x_i;
+    let mut msgs1 = vec![];
+    for &receiver in helpers.iter().filter(|&&k| k != party_index) {
+        let mask = Scalar::<Ed25519>::random();
+        blinded_share = blinded_share - &mask;
+        msgs1.push(RecoverMsg1 {
+            sender: party_index,
+            receiver,
+            mask,
+        });
+    }
+
+    let context1 = RecoverContext1 {
+        helpers,
+        lost_index,
+        blinded_share,
+    };
+    Ok((context1, msgs1))
+}
+
+pub fn recover_phase2(
+    sign_key: &SignKey,
+    context1: RecoverContext1,
+    mut msgs1: Vec<RecoverMsg1>,
+) -> TssResult<RecoverMsg2> {
+    msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
+
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let expected: Vec<_> = context1
+        .helpers
+        .iter()
+        .copied()
+        .filter(|&k| k != party_index)
+        .collect();
+    check_senders(msgs1.iter().map(|msg| msg.sender), &expected)?;
+    check_receivers(
+        msgs1.iter().map(|msg| (msg.sender, msg.receiver)),
+        party_index,
+    )?;
+
+    let blinded_share = msgs1
+        .iter()
+        .fold(context1.blinded_share, |acc, msg| acc + &msg.mask);
+
+    Ok(RecoverMsg2 {
+        sender: party_index,
+        receiver: context1.lost_index,
+        helpers: context1.helpers,
+        params: sign_key.params.clone(),
+        agg_pubkey: sign_key.agg_pubkey.clone(),
+        vss_schemes: sign_key.vss_schemes.clone(),
+        blinded_share,
+    })
+}
+
+/// Run on the new device for `party_index`, with one message from every helper.
+pub fn recover_phase3(party_index: u16, mut msgs2: Vec<RecoverMsg2>) -> TssResult<SignKey> {
+    msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+
+    let first = msgs2.first().ok_or(TssError::InputsLengthUnmatch)?;
+    let helpers = first.helpers.clone();
+    let params = first.params.clone();
+    let agg_pubkey = first.agg_pubkey.clone();
+    let vss_scheme = sum_vss_schemes(&first.vss_schemes)?;
+    check_helpers(&helpers, party_index, &params)?;
+    if vss_scheme.commitments.len() != usize::from(params.threshold) + 1 {
+        return Err(TssError::SpecificError(
+            "invalid recovery commitments".into(),
+        ));
+    }
+    check_senders(msgs2.iter().map(|msg| msg.sender), &helpers)?;
+    check_receivers(
+        msgs2.iter().map(|msg| (msg.sender, msg.receiver)),
+        party_index,
+    )?;
+
+    let mut x_i = Scalar::<Ed25519>::zero();
+    for msg in &msgs2 {
+        if msg.helpers != helpers
+            || msg.params.threshold != params.threshold
+            || msg.params.share_count != params.share_count
+            || msg.agg_pubkey != agg_pubkey
+            || sum_vss_schemes(&msg.vss_schemes)?.commitments != vss_scheme.commitments
+        {
+            return Err(TssError::SpecificError(format!(
+                "inconsistent recovery message from party {}",
+                msg.sender
+            )));
+        }
+        x_i = x_i + &msg.blinded_share;
+    }
+
+    if vss_scheme.commitments[0] != agg_pubkey {
+        return Err(TssError::SpecificError("recovered key mismatch".into()));
+    }
+    vss_scheme
+        .validate_share(&x_i, party_index)
+        .map_err(|_| TssError::SpecificError("recovered share is inconsistent".into()))?;
+
+    let keypair = Keys::phase1_create(party_index);
+    Ok(SignKey {
+        params,
+        agg_pubkey: agg_pubkey.clone(),
+        vss_schemes: vec![vss_scheme],
+        secret_share: SecretShare {
+            shared_keys: shared_keys(&keypair, &agg_pubkey, x_i)?,
+            keypair,
+        },
+    })
+}
@@ -0,0 +1,190 @@
+use curv::{
+    cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS,
+    elliptic::curves::{Ed25519, Scalar},
+};
+use multi_party_eddsa::protocols::thresholdsig::{Keys, Parameters};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    echo::{echo_phase1, echo_phase2, EchoMsg},
+    error::TssError,
+    keygen::{shared_keys, sum_vss_schemes, SecretShare, SignKey},
+    validate::{check_receivers, check_senders, check_session, session_tag},
+    TssResult,
+};
+
+// Every holder deals a random sharing of zero and adds the shares it receives
+// to its own, so the shared secret and `agg_pubkey` stay the same while any
+// share leaked before the refresh becomes useless. The refreshed key keeps a
+// single `vss_schemes` entry, the sum of all commitments.
+//
+// The commitments are broadcast (`RefreshMsg1`) and the shares sent point to
+// point (`RefreshMsg2`). A dealer could still show different commitments to
+// different parties, each matching that party's share, so the parties echo
+// the commitments they saw and only accept the refresh once all echoes agree.
+
+// party one structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshContext1 {
+    pub params: Parameters,
+    pub session: [u8; 32],
+    pub keypair: Keys,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshMsg1 {
+    pub sender: u16,
+    pub session: [u8; 32],
+    pub vss_scheme: VerifiableSS<Ed25519>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshMsg2 {
+    pub sender: u16,
+    pub receiver: u16,
+    pub session: [u8; 32],
+    pub secret_share: Scalar<Ed25519>,
+}
+
+//party two structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RefreshContext2 {
+    pub params: Parameters,
+    pub keypair: Keys,
+    pub msgs1: Vec<RefreshMsg1>,
+    pub x_i: Scalar<Ed25519>,
+    pub vss_scheme: VerifiableSS<Ed25519>,
+}
+
+/// `session_id` must be fresh and shared by all parties of this refresh.
+pub fn refresh_phase1(
+    sign_key: &SignKey,
+    session_id: &[u8],
+) -> TssResult<(RefreshContext1, RefreshMsg1, Vec<RefreshMsg2>)> {
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let t = sign_key.params.threshold;
+    let n = sign_key.params.share_count;
+    let session = session_tag(
+        b"tss-eddsa refresh",
+        session_id,
+        &[
+            &t.to_be_bytes(),
+            &n.to_be_bytes(),
+            &sign_key.agg_pubkey.to_bytes(true),
+        ],
+    );
+    let (vss_scheme, secret_shares) = VerifiableSS::share(t, n, &Scalar::zero());
+
+    let msg1 = RefreshMsg1 {
+        sender: party_index,
+        session,
+        vss_scheme,
+    };
+    let mut msgs2 = vec![];
+    for i in 0..usize::from(n) {
+        msgs2.push(RefreshMsg2 {
+            sender: party_index,
+            receiver: i as u16 + 1,
+            session,
+            secret_share: secret_shares[i].clone(),
+        });
+    }
+
+    // the keygen contribution is as sensitive as the share, replace it as well
+    let context1 = RefreshContext1 {
+        params: sign_key.params.clone(),
+        session,
+        keypair: Keys::phase1_create(party_index),
+    };
+    Ok((context1, msg1, msgs2))
+}
+
+/// Checks the dealings and returns the echo of the broadcast commitments,
+/// to be sent to every party over an authenticated channel.
+pub fn refresh_phase2(
+    sign_key: &SignKey,
+    context1: RefreshContext1,
+    mut msgs1: Vec<RefreshMsg1>,
+    mut msgs2: Vec<RefreshMsg2>,
+) -> TssResult<(RefreshContext2, EchoMsg)> {
+    msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
+    msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+
+    let party_index = sign_key.secret_share.keypair.party_index;
+    let t = context1.params.threshold;
+    let n = context1.params.share_count;
+    let parties: Vec<_> = (1..=n).collect();
+    check_senders(msgs1.iter().map(|msg| msg.sender), &parties)?;
+    check_senders(msgs2.iter().map(|msg| msg.sender), &parties)?;
+    check_receivers(
+        msgs2.iter().map(|msg| (msg.sender, msg.receiver)),
+        party_index,
+    )?;
+    check_session(
+        msgs1.iter().map(|msg| (msg.sender, msg.session)),
+        &context1.session,
+    )?;
+    check_session(
+        msgs2.iter().map(|msg| (msg.sender, msg.session)),
+        &context1.session,
+    )?;
+
+    let mut x_i = sign_key.secret_share.shared_keys.x_i.clone();
+    let mut vss_schemes = sign_key.vss_schemes.clone();
+    for (msg1, msg2) in msgs1.iter().zip(&msgs2) {
+        let vss = &msg1.vss_scheme;
+        if vss.parameters.threshold != t
+            || vss.parameters.share_count != n
+            || vss.commitments.len() != usize::from(t) + 1
+            || !vss.commitments[0].is_zero()
+        {
+            return Err(TssError::SpecificError(format!(
+                "invalid zero sharing from party {}",
+                msg1.sender
+            )));
+        }
+        vss.validate_share(&msg2.secret_share, party_index)
+            .map_err(|_| {
+                TssError::SpecificError(format!("invalid refresh share from party {}", msg1.sender))
+            })?;
+
+        x_i = x_i + &msg2.secret_share;
+        vss_schemes.push(vss.clone());
+    }
+
+    let vss_scheme = sum_vss_schemes(&vss_schemes)?;
+    vss_scheme
+        .validate_share(&x_i, party_index)
+        .map_err(|_| TssError::SpecificError("refreshed share is inconsistent".into()))?;
+
+    let echo = echo_phase1(party_index, &msgs1)?;
+    let context2 = RefreshContext2 {
+        params: context1.params,
+        keypair: context1.keypair,
+        msgs1,
+        x_i,
+        vss_scheme,
+    };
+    Ok((context2, echo))
+}
+
+/// Finishes the refresh once every party's echo matches the commitments
+/// this party saw. On `Equivocation` the old key must be kept.
+pub fn refresh_phase3(
+    sign_key: &SignKey,
+    context2: RefreshContext2,
+    echoes: Vec<EchoMsg>,
+) -> TssResult<SignKey> {
+    let parties: Vec<_> = (1..=context2.params.share_count).collect();
+    echo_phase2(&parties, &context2.msgs1, &echoes)?;
+
+    Ok(SignKey {
+        params: context2.params,
+        agg_pubkey: sign_key.agg_pubkey.clone(),
+        vss_schemes: vec![context2.vss_scheme],
+        secret_share: SecretShare {
+            shared_keys: shared_keys(&context2.keypair, &sign_key.agg_pubkey, context2.x_i)?,
+            keypair: context2.keypair,
+        },
+    })
+}
@@ -0,0 +1,244 @@
+use curv::{
+    cryptographic_primitives::secret_sharing::feldman_vss::{ShamirSecretSharing, VerifiableSS},
+    elliptic::curves::{Ed25519, Point, Scalar},
+};
+use multi_party_eddsa::protocols::thresholdsig::{Keys, Parameters};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    echo::{echo_phase1, echo_phase2_from, EchoMsg},
+    error::TssError,
+    keygen::{shared_keys, sum_vss_schemes, SecretShare, SignKey},
+    validate::{
+        check_params, check_parties, check_receivers, check_senders, check_session, session_tag,
+    },
+    TssResult,
+};
+
+// Each online member `i` of the old committee turns its share into an additive
+// share `lambda_i * x_i` of the secret and deals it to the new committee with a
+// fresh `(t', n')` Feldman VSS. New party `j` adds up what it receives, so the
+// secret and `agg_pubkey` carry over. Any `t + 1` old parties are enough, and a
+// party may sit in both committees. Old shares should be deleted afterwards.
+//
+// The new committee cannot check the old commitments and the dealings against
+// anything but each other, so they are broadcast (`ReshareMsg1`) and echoed
+// among the new committee before anyone accepts its share. Only the shares
+// travel point to point (`ReshareMsg2`).
+
+// old committee structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReshareMsg1 {
+    pub sender: u16,
+    pub session: [u8; 32],
+    pub old_parties: Vec<u16>,
+    pub agg_pubkey: Point<Ed25519>,
+    pub old_vss_schemes: Vec<VerifiableSS<Ed25519>>,
+    pub vss_scheme: VerifiableSS<Ed25519>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReshareMsg2 {
+    pub sender: u16,
+    pub receiver: u16,
+    pub session: [u8; 32],
+    pub secret_share: Scalar<Ed25519>,
+}
+
+// new committee structures
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReshareContext2 {
+    pub params: Parameters,
+    pub party_index: u16,
+    pub agg_pubkey: Point<Ed25519>,
+    pub msgs1: Vec<ReshareMsg1>,
+    pub x_i: Scalar<Ed25519>,
+    pub vss_scheme: VerifiableSS<Ed25519>,
+}
+
+fn reshare_session(session_id: &[u8], t: u16, n: u16) -> [u8; 32] {
+    session_tag(
+        b"tss-eddsa reshare",
+        session_id,
+        &[&t.to_be_bytes(), &n.to_be_bytes()],
+    )
+}
+
+/// Run by each of the online old parties `old_parties` (at least `t + 1`), for
+/// a new committee of `n` parties with threshold `t`. `session_id` must be
+/// fresh and shared by both committees.
+pub fn reshare_phase1(
+    sign_key: &SignKey,
+    mut old_parties: Vec<u16>,
+    t: u16,
+    n: u16,
+    session_id: &[u8],
+) -> TssResult<(ReshareMsg1, Vec<ReshareMsg2>)> {
+    old_parties.sort();
+    old_parties.dedup();
+
+    let party_index = sign_key.secret_share.keypair.party_index;
+    check_parties(&sign_key.params, &old_parties, party_index)?;
+    if t >= n {
+        return Err(TssError::InvalidParameters);
+    }
+
+    let old_parties_indexs: Vec<_> = old_parties.iter().map(|a| a - 1).collect();
+    let params = ShamirSecretSharing {
+        threshold: sign_key.params.threshold,
+        share_count: sign_key.params.share_count,
+    };
+    let lambda = VerifiableSS::<Ed25519>::map_share_to_new_params(
+        &params,
+        party_index - 1,
+        &old_parties_indexs,
+    );
+    let additive_share = lambda * &sign_key.secret_share.shared_keys.x_i;
+
+    let (vss_scheme, secret_shares) = VerifiableSS::share(t, n, &additive_share);
+
+    let session = reshare_session(session_id, t, n);
+    let msg1 = ReshareMsg1 {
+        sender: party_index,
+        session,
+        old_parties,
+        agg_pubkey: sign_key.agg_pubkey.clone(),
+        old_vss_schemes: sign_key.vss_schemes.clone(),
+        vss_scheme,
+    };
+    let mut msgs2 = vec![];
+    for i in 0..usize::from(n) {
+        msgs2.push(ReshareMsg2 {
+            sender: party_index,
+            receiver: i as u16 + 1,
+            session,
+            secret_share: secret_shares[i].clone(),
+        });
+    }
+    Ok((msg1, msgs2))
+}
+
+/// Run by each member of the new committee, with one message of each kind
+/// from every old party listed in `old_parties`. Returns the echo of the
+/// broadcast messages, to be sent to the rest of the new committee over an
+/// authenticated channel.
+pub fn reshare_phase2(
+    t: u16,
+    n: u16,
+    party_index: u16,
+    session_id: &[u8],
+    mut msgs1: Vec<ReshareMsg1>,
+    mut msgs2: Vec<ReshareMsg2>,
+) -> TssResult<(ReshareContext2, EchoMsg)> {
+    msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
+    msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+
+    check_params(t, n, party_index)?;
+    let session = reshare_session(session_id, t, n);
+    check_session(msgs1.iter().map(|msg| (msg.sender, msg.session)), &session)?;
+    check_session(msgs2.iter().map(|msg| (msg.sender, msg.session)), &session)?;
+
+    let first = msgs1.first().ok_or(TssError::InputsLengthUnmatch)?;
+    let mut old_parties = first.old_parties.clone();
+    old_parties.sort();
+    old_parties.dedup();
+    let agg_pubkey = first.agg_pubkey.clone();
+    let old_vss_scheme = sum_vss_schemes(&first.old_vss_schemes)?;
+    if old_parties.len() <= usize::from(old_vss_scheme.parameters.threshold)
+        || old_parties != first.old_parties
+        || old_parties[0] == 0
+        || old_parties[old_parties.len() - 1] > old_vss_scheme.parameters.share_count
+        || old_vss_scheme.commitments.len() != usize::from(old_vss_scheme.parameters.threshold) + 1
+        || old_vss_scheme.commitments[0] != agg_pubkey
+    {
+        return Err(TssError::SpecificError("invalid old committee".into()));
+    }
+    check_senders(msgs1.iter().map(|msg| msg.sender), &old_parties)?;
+    check_senders(msgs2.iter().map(|msg| msg.sender), &old_parties)?;
+    check_receivers(
+        msgs2.iter().map(|msg| (msg.sender, msg.receiver)),
+        party_index,
+    )?;
+
+    let old_parties_indexs: Vec<_> = old_parties.iter().map(|a| a - 1).collect();
+    let mut x_i = Scalar::<Ed25519>::zero();
+    let mut vss_schemes = vec![];
+    for ((msg, msg2), &sender) in msgs1.iter().zip(&msgs2).zip(&old_parties) {
+        if msg.old_parties != old_parties
+            || msg.agg_pubkey != agg_pubkey
+            || sum_vss_schemes(&msg.old_vss_schemes)?.commitments != old_vss_scheme.commitments
+        {
+            return Err(TssError::SpecificError(format!(
+                "inconsistent reshare message from party {}",
+                msg.sender
+            )));
+        }
+
+        // the dealt secret must be the sender's Lagrange-weighted old share
+        let lambda = VerifiableSS::<Ed25519>::map_share_to_new_params(
+            &old_vss_scheme.parameters,
+            sender - 1,
+            &old_parties_indexs,
+        );
+        let vss = &msg.vss_scheme;
+        if vss.parameters.threshold != t
+            || vss.parameters.share_count != n
+            || vss.commitments.len() != usize::from(t) + 1
+            || vss.commitments[0] != old_vss_scheme.get_point_commitment(sender) * &lambda
+        {
+            return Err(TssError::SpecificError(format!(
+                "invalid reshare dealing from party {}",
+                sender
+            )));
+        }
+        vss.validate_share(&msg2.secret_share, party_index)
+            .map_err(|_| {
+                TssError::SpecificError(format!("invalid reshare share from party {}", sender))
+            })?;
+
+        x_i = x_i + &msg2.secret_share;
+        vss_schemes.push(vss.clone());
+    }
+
+    let vss_scheme = sum_vss_schemes(&vss_schemes)?;
+    if vss_scheme.commitments[0] != agg_pubkey {
+        return Err(TssError::SpecificError("reshared key mismatch".into()));
+    }
+    vss_scheme
+        .validate_share(&x_i, party_index)
+        .map_err(|_| TssError::SpecificError("reshared share is inconsistent".into()))?;
+
+    let echo = echo_phase1(party_index, &msgs1)?;
+    let context2 = ReshareContext2 {
+        params: Parameters {
+            threshold: t,
+            share_count: n,
+        },
+        party_index,
+        agg_pubkey,
+        msgs1,
+        x_i,
+        vss_scheme,
+    };
+    Ok((context2, echo))
+}
+
+/// Finishes the reshare once the echo of every member of the new committee
+/// matches the messages this party saw. On `Equivocation` the old committee
+/// must keep its shares.
+pub fn reshare_phase3(context2: ReshareContext2, echoes: Vec<EchoMsg>) -> TssResult<SignKey> {
+    let old_parties: Vec<_> = context2.msgs1.iter().map(|msg| msg.sender).collect();
+    let parties: Vec<_> = (1..=context2.params.share_count).collect();
+    echo_phase2_from(&old_parties, &parties, &context2.msgs1, &echoes)?;
+
+    let keypair = Keys::phase1_create(context2.party_index);
+    Ok(SignKey {
+        params: context2.params,
+        agg_pubkey: context2.agg_pubkey.clone(),
+        vss_schemes: vec![context2.vss_scheme],
+        secret_share: SecretShare {
+            shared_keys: shared_keys(&keypair, &context2.agg_pubkey, context2.x_i)?,
+            keypair,
+        },
+    })
+}
@@ -1,4 +1,5 @@
 use curv::{
+    arithmetic::traits::*,
     cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS,
     elliptic::curves::{Ed25519, Point, Scalar},
     BigInt,
@@ -7,16 +8,25 @@ use multi_party_eddsa::protocols::{
     thresholdsig::{self, EphemeralKey, KeyGenBroadcastMessage1, LocalSig, Parameters},
     Signature,
 };
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
 
-use crate::{error::TssError, keygen::SignKey, TssResult};
+use crate::{
+    error::TssError,
+    keygen::{sum_vss_schemes, SignKey},
+    validate::{check_parties, check_receivers, check_senders, check_session, session_tag},
+    TssResult,
+};
 
 // party one structures
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignContext1 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub parties: Vec<u16>,
     pub message: Vec<u8>,
+    pub variant: Ed25519Variant,
     pub nonce_key: EphemeralKey,
     pub first_msg_blind: BigInt,
 }
@@ -24,6 +34,7 @@ pub struct SignContext1 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignMsg1 {
     pub sender: u16,
+    pub session: [u8; 32],
     pub msg: KeyGenBroadcastMessage1,
 }
 
@@ -31,8 +42,10 @@ pub struct SignMsg1 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignContext2 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub parties: Vec<u16>,
     pub message: Vec<u8>,
+    pub variant: Ed25519Variant,
     pub nonce_key: EphemeralKey,
     pub comm_vec: Vec<KeyGenBroadcastMessage1>,
 }
@@ -41,6 +54,7 @@ pub struct SignContext2 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignMsg2 {
     pub sender: u16,
+    pub session: [u8; 32],
     pub nonce_pk: Point<Ed25519>,
     pub first_msg_blind: BigInt,
 }
@@ -49,8 +63,10 @@ pub struct SignMsg2 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignContext3 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub parties: Vec<u16>,
     pub message: Vec<u8>,
+    pub variant: Ed25519Variant,
     pub nonce_key: EphemeralKey,
     pub nonce_keys_vec: Vec<Point<Ed25519>>,
     pub agg_nonce: Point<Ed25519>,
@@ -61,6 +77,7 @@ pub struct SignContext3 {
 pub struct SignMsg3 {
     pub sender: u16,
     pub receiver: u16,
+    pub session: [u8; 32],
     pub secret_share: Scalar<Ed25519>,
     pub vss_scheme: VerifiableSS<Ed25519>,
 }
@@ -69,8 +86,10 @@ pub struct SignMsg3 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignContext4 {
     pub params: Parameters,
+    pub session: [u8; 32],
     pub parties: Vec<u16>,
     pub message: Vec<u8>,
+    pub variant: Ed25519Variant,
     pub agg_nonce: Point<Ed25519>,
     pub nonce_vss_schemes: Vec<VerifiableSS<Ed25519>>,
 }
@@ -79,21 +98,224 @@ pub struct SignContext4 {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SignMsg4 {
     pub sender: u16,
+    pub session: [u8; 32],
     pub partial_sig: LocalSig,
 }
 
+/// RFC 8032 signing mode. With `Ph` the signed `message` is the SHA-512
+/// digest of the data, so contexts stay small for large payloads.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Ed25519Variant {
+    Pure,
+    Ctx(Vec<u8>),
+    Ph(Vec<u8>),
+}
+
+impl Ed25519Variant {
+    /// `dom2(F, C)`, empty for plain Ed25519.
+    fn dom2(&self) -> Vec<u8> {
+        let (flag, context) = match self {
+            Ed25519Variant::Pure => return vec![],
+            Ed25519Variant::Ctx(context) => (0u8, context),
+            Ed25519Variant::Ph(context) => (1u8, context),
+        };
+        [
+            &b"SigEd25519 no Ed25519 collisions"[..],
+            &[flag, context.len() as u8],
+            context,
+        ]
+        .concat()
+    }
+
+    fn check(&self) -> TssResult<()> {
+        match self {
+            Ed25519Variant::Ctx(context) if context.is_empty() || context.len() > 255 => {
+                Err(TssError::InvalidParameters)
+            }
+            Ed25519Variant::Ph(context) if context.len() > 255 => Err(TssError::InvalidParameters),
+            _ => Ok(()),
+        }
+    }
+
+    /// `k = H(dom2 || R || A || message)`.
+    pub fn challenge(
+        &self,
+        nonce: &Point<Ed25519>,
+        pubkey: &Point<Ed25519>,
+        message: &[u8],
+    ) -> Scalar<Ed25519> {
+        if *self == Ed25519Variant::Pure {
+            return Signature::k(nonce, pubkey, message);
+        }
+        let mut hash = Sha512::new()
+            .chain(self.dom2())
+            .chain(&*nonce.to_bytes(true))
+            .chain(&*pubkey.to_bytes(true))
+            .chain(message)
+            .finalize()
+            .to_vec();
+        hash.reverse();
+        Scalar::from_bigint(&BigInt::from_bytes(&hash))
+    }
+
+    pub fn verify(
+        &self,
+        sig: &Signature,
+        message: &[u8],
+        pubkey: &Point<Ed25519>,
+    ) -> TssResult<()> {
+        let k = self.challenge(&sig.R, pubkey, message);
+        if Point::<Ed25519>::generator() * &sig.s != &sig.R + pubkey * &k {
+            return Err(TssError::SpecificError("invalid signature".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Hedged nonce `r_i = H(prefix || rand || session_id || parties || message)`.
+///
+/// The fresh randomness keeps `R_i` from repeating when the same message is
+/// signed by different subsets, and the key prefix keeps the nonce secret even
+/// if the randomness source is weak.
+fn hedged_nonce_key(
+    sign_key: &SignKey,
+    parties: &[u16],
+    message: &[u8],
+    session_id: &[u8],
+) -> TssResult<EphemeralKey> {
+    let keypair = &sign_key.secret_share.keypair;
+    let mut keypair_value = serde_json::to_value(keypair)?;
+    let prefix: Scalar<Ed25519> =
+        serde_json::from_value(keypair_value["keypair"]["expanded_private_key"]["prefix"].take())?;
+    let randomness: [u8; 32] = rand::thread_rng().gen();
+
+    let mut hasher = Sha512::new();
+    hasher.update(&*prefix.to_bytes());
+    hasher.update(randomness);
+    hasher.update((session_id.len() as u64).to_be_bytes());
+    hasher.update(session_id);
+    for party in parties {
+        hasher.update(party.to_be_bytes());
+    }
+    hasher.update(message);
+    let r_i = Scalar::<Ed25519>::from_bigint(&BigInt::from_bytes(&hasher.finalize()));
+    let nonce_pk = Point::<Ed25519>::generator() * &r_i;
+
+    Ok(EphemeralKey {
+        r_i,
+        R_i: nonce_pk,
+        party_index: keypair.party_index,
+    })
+}
+
+/// Starts signing with a hedged nonce bound to `session_id` and the signer set.
 pub fn sign_phase1(
+    sign_key: &SignKey,
+    parties: Vec<u16>,
+    message: &[u8],
+    session_id: &[u8],
+) -> TssResult<(SignContext1, SignMsg1)> {
+    sign_phase1_hedged(sign_key, parties, message, Ed25519Variant::Pure, session_id)
+}
+
+/// Starts an Ed25519ctx signature of `message` under a non-empty `context`.
+pub fn sign_phase1_ctx(
+    sign_key: &SignKey,
+    parties: Vec<u16>,
+    message: &[u8],
+    context: &[u8],
+    session_id: &[u8],
+) -> TssResult<(SignContext1, SignMsg1)> {
+    let variant = Ed25519Variant::Ctx(context.to_vec());
+    sign_phase1_hedged(sign_key, parties, message, variant, session_id)
+}
+
+/// Starts an Ed25519ph signature; `digest` is the SHA-512 hash of the data.
+pub fn sign_phase1_ph(
+    sign_key: &SignKey,
+    parties: Vec<u16>,
+    digest: &[u8; 64],
+    context: &[u8],
+    session_id: &[u8],
+) -> TssResult<(SignContext1, SignMsg1)> {
+    let variant = Ed25519Variant::Ph(context.to_vec());
+    sign_phase1_hedged(sign_key, parties, digest, variant, session_id)
+}
+
+fn sign_phase1_hedged(
     sign_key: &SignKey,
     mut parties: Vec<u16>,
     message: &[u8],
+    variant: Ed25519Variant,
+    session_id: &[u8],
 ) -> TssResult<(SignContext1, SignMsg1)> {
     parties.sort_by(|a, b| a.cmp(&b));
+    check_parties(
+        &sign_key.params,
+        &parties,
+        sign_key.secret_share.keypair.party_index,
+    )?;
+    variant.check()?;
+
+    let domain_message = [variant.dom2(), message.to_vec()].concat();
+    let nonce_key = hedged_nonce_key(sign_key, &parties, &domain_message, session_id)?;
+    sign_phase1_with_nonce(sign_key, parties, message, variant, session_id, nonce_key)
+}
+
+/// Starts signing with a nonce derived from the key and message only.
+///
+/// Unsafe whenever any co-signer is untrusted: the nonce only depends on the
+/// message, so a co-signer that aborts and restarts the same message with a
+/// different nonce of its own gets two responses under one `r_i` and learns
+/// this party's key share. The same happens when a message is signed twice
+/// with different `parties`. Use `sign_phase1` unless every signer is trusted.
+pub fn sign_phase1_deterministic(
+    sign_key: &SignKey,
+    mut parties: Vec<u16>,
+    message: &[u8],
+    session_id: &[u8],
+) -> TssResult<(SignContext1, SignMsg1)> {
+    parties.sort_by(|a, b| a.cmp(&b));
+    check_parties(
+        &sign_key.params,
+        &parties,
+        sign_key.secret_share.keypair.party_index,
+    )?;
 
     let nonce_key = EphemeralKey::ephermeral_key_create_from_deterministic_secret(
         &sign_key.secret_share.keypair,
         message,
         sign_key.secret_share.keypair.party_index,
     );
+    sign_phase1_with_nonce(
+        sign_key,
+        parties,
+        message,
+        Ed25519Variant::Pure,
+        session_id,
+        nonce_key,
+    )
+}
+
+fn sign_phase1_with_nonce(
+    sign_key: &SignKey,
+    parties: Vec<u16>,
+    message: &[u8],
+    variant: Ed25519Variant,
+    session_id: &[u8],
+    nonce_key: EphemeralKey,
+) -> TssResult<(SignContext1, SignMsg1)> {
+    let parties_bytes: Vec<_> = parties.iter().flat_map(|i| i.to_be_bytes()).collect();
+    let session = session_tag(
+        b"tss-eddsa sign",
+        session_id,
+        &[
+            &sign_key.agg_pubkey.to_bytes(true),
+            &parties_bytes,
+            &variant.dom2(),
+            message,
+        ],
+    );
 
     let (first_msg, first_msg_blind) = nonce_key.phase1_broadcast();
     let params = Parameters {
@@ -103,14 +325,17 @@ pub fn sign_phase1(
 
     let sign_context1 = SignContext1 {
         params,
+        session,
         parties,
         message: message.to_vec(),
+        variant,
         nonce_key,
         first_msg_blind,
     };
 
     let sign_msg1 = SignMsg1 {
         sender: sign_key.secret_share.keypair.party_index,
+        session,
         msg: first_msg,
     };
 
@@ -123,19 +348,27 @@ pub fn sign_phase2(
     mut msgs1: Vec<SignMsg1>,
 ) -> TssResult<(SignContext2, SignMsg2)> {
     msgs1.sort_by(|a, b| a.sender.cmp(&b.sender));
+    check_senders(msgs1.iter().map(|msg| msg.sender), &context1.parties)?;
+    check_session(
+        msgs1.iter().map(|msg| (msg.sender, msg.session)),
+        &context1.session,
+    )?;
 
     let comm_vec = msgs1.into_iter().map(|msg| msg.msg).collect();
 
     let msg2 = SignMsg2 {
         sender: sign_key.secret_share.keypair.party_index,
+        session: context1.session,
         nonce_pk: context1.nonce_key.R_i.clone(),
         first_msg_blind: context1.first_msg_blind,
     };
 
     let context2 = SignContext2 {
         params: context1.params,
+        session: context1.session,
         parties: context1.parties,
         message: context1.message,
+        variant: context1.variant,
         nonce_key: context1.nonce_key,
         comm_vec,
     };
@@ -149,6 +382,11 @@ pub fn sign_phase3(
     mut msgs2: Vec<SignMsg2>,
 ) -> TssResult<(SignContext3, Vec<SignMsg3>)> {
     msgs2.sort_by(|a, b| a.sender.cmp(&b.sender));
+    check_senders(msgs2.iter().map(|msg| msg.sender), &context2.parties)?;
+    check_session(
+        msgs2.iter().map(|msg| (msg.sender, msg.session)),
+        &context2.session,
+    )?;
 
     let (blind_vec, nonce_keys_vec): (Vec<_>, Vec<_>) = msgs2
         .into_iter()
@@ -182,6 +420,7 @@ pub fn sign_phase3(
         msg3.push(SignMsg3 {
             sender: sign_key.secret_share.keypair.party_index,
             receiver,
+            session: context2.session,
             secret_share: nonce_secret_share[index].clone(),
             vss_scheme: nonce_vss_scheme.clone(),
         });
@@ -189,8 +428,10 @@ pub fn sign_phase3(
 
     let context3 = SignContext3 {
         params: context2.params,
+        session: context2.session,
         parties: context2.parties,
         message: context2.message,
+        variant: context2.variant,
         nonce_key: context2.nonce_key,
         nonce_keys_vec,
         agg_nonce,
@@ -204,6 +445,15 @@ pub fn sign_phase4(
     mut msgs3: Vec<SignMsg3>,
 ) -> TssResult<(SignContext4, SignMsg4)> {
     msgs3.sort_by(|a, b| a.sender.cmp(&b.sender));
+    check_senders(msgs3.iter().map(|msg| msg.sender), &context3.parties)?;
+    check_session(
+        msgs3.iter().map(|msg| (msg.sender, msg.session)),
+        &context3.session,
+    )?;
+    check_receivers(
+        msgs3.iter().map(|msg| (msg.sender, msg.receiver)),
+        sign_key.secret_share.keypair.party_index,
+    )?;
 
     let secret_shares_vec = msgs3
         .iter()
@@ -232,28 +482,64 @@ pub fn sign_phase4(
         sign_key.secret_share.keypair.party_index,
     )?;
 
-    let partial_sig = LocalSig::compute(
-        &context3.message,
-        &shared_keys,
-        &sign_key.secret_share.shared_keys,
-    );
+    // `LocalSig::compute` only knows plain Ed25519, so build it with our own
+    // challenge; its fields are private so it goes through serde
+    let k =
+        context3
+            .variant
+            .challenge(&context3.agg_nonce, &sign_key.agg_pubkey, &context3.message);
+    let gamma_i = &shared_keys.x_i + &k * &sign_key.secret_share.shared_keys.x_i;
+    let partial_sig: LocalSig = serde_json::from_value(serde_json::json!({
+        "gamma_i": gamma_i,
+        "k": k,
+    }))?;
 
     let context4 = SignContext4 {
         params: context3.params,
+        session: context3.session,
         parties: context3.parties,
         message: context3.message,
+        variant: context3.variant,
         agg_nonce: context3.agg_nonce,
         nonce_vss_schemes: vss_scheme_vec,
     };
 
     let msg4 = SignMsg4 {
         sender: sign_key.secret_share.keypair.party_index,
+        session: context3.session,
         partial_sig,
     };
 
     Ok((context4, msg4))
 }
 
+/// Checks `gamma_i * G = R_i + k * X_i` for every sender, where `R_i` and `X_i`
+/// are the sender's public nonce and key shares, and returns who failed.
+fn partial_sig_culprits(
+    sign_key: &SignKey,
+    context4: &SignContext4,
+    msgs4: &[SignMsg4],
+) -> TssResult<Vec<u16>> {
+    let key_vss = sum_vss_schemes(&sign_key.vss_schemes)?;
+    let nonce_vss = sum_vss_schemes(&context4.nonce_vss_schemes)?;
+    let k =
+        context4
+            .variant
+            .challenge(&context4.agg_nonce, &sign_key.agg_pubkey, &context4.message);
+
+    let mut culprits = vec![];
+    for msg in msgs4 {
+        let gamma_i: Scalar<Ed25519> =
+            serde_json::from_value(serde_json::to_value(&msg.partial_sig)?["gamma_i"].take())?;
+        let expected = nonce_vss.get_point_commitment(msg.sender)
+            + key_vss.get_point_commitment(msg.sender) * &k;
+        if Point::<Ed25519>::generator() * &gamma_i != expected {
+            culprits.push(msg.sender);
+        }
+    }
+    Ok(culprits)
+}
+
 pub fn sign_phase5(
     sign_key: &SignKey,
     context4: SignContext4,
@@ -261,6 +547,16 @@ pub fn sign_phase5(
 ) -> TssResult<Signature> {
     msgs4.sort_by(|a, b| a.sender.cmp(&b.sender));
 
+    check_senders(msgs4.iter().map(|msg| msg.sender), &context4.parties)?;
+    check_session(
+        msgs4.iter().map(|msg| (msg.sender, msg.session)),
+        &context4.session,
+    )?;
+    let culprits = partial_sig_culprits(sign_key, &context4, &msgs4)?;
+    if !culprits.is_empty() {
+        return Err(TssError::InvalidPartialSignatures(culprits));
+    }
+
     let partial_sigs: Vec<_> = msgs4.into_iter().map(|msg| msg.partial_sig).collect();
     let parties_indexs: Vec<_> = context4.parties.iter().map(|a| a - 1).collect();
     let vss_sum_sigs = LocalSig::verify_local_sigs(
@@ -277,7 +573,9 @@ pub fn sign_phase5(
         context4.agg_nonce,
     );
 
-    sig.verify(&context4.message, &sign_key.agg_pubkey)?;
+    context4
+        .variant
+        .verify(&sig, &context4.message, &sign_key.agg_pubkey)?;
 
     return Ok(sig);
 }
@@ -1,16 +1,43 @@
 #[cfg(test)]
 mod tests {
-    use curv::elliptic::curves::{Ed25519, Point};
+    use curv::{
+        arithmetic::Converter,
+        cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS,
+        elliptic::curves::{Ed25519, Point, Scalar},
+        BigInt,
+    };
     use ed25519_dalek::Verifier;
     use itertools::Itertools;
     use multi_party_eddsa::protocols::Signature;
     use rand::{thread_rng, Rng};
+    use sha2::{Digest, Sha512};
 
     use crate::{
-        keygen::{keygen_phase1, keygen_phase2, keygen_phase3, keygen_phase4},
-        sign::{sign_phase1, sign_phase2, sign_phase3, sign_phase4, sign_phase5},
+        address::{pubkey_base58, pubkey_from_base58},
+        channel::{open, open_broadcast, seal, sign_broadcast, IdentityKey},
+        dealer::{dealer_split_expanded, dealer_split_seed},
+        derive::{derive_pubkey, derive_sign_key},
+        echo::{echo_phase1, echo_phase2},
+        error::TssError,
+        frost::{frost_phase1, frost_phase2, frost_phase3},
+        keygen::{
+            keygen_complaint_phase1, keygen_complaint_phase2, keygen_complaint_phase3,
+            keygen_phase1, keygen_phase2, keygen_phase3, keygen_phase4, KeyGenMsg1, KeyGenMsg3,
+            SignKey,
+        },
+        recover::{recover_phase1, recover_phase2, recover_phase3},
+        refresh::{refresh_phase1, refresh_phase2, refresh_phase3},
+        reshare::{reshare_phase1, reshare_phase2, reshare_phase3},
+        sign::{
+            sign_phase1, sign_phase1_ctx, sign_phase1_deterministic, sign_phase1_ph, sign_phase2,
+            sign_phase3, sign_phase4, sign_phase5, Ed25519Variant, SignContext1, SignContext4,
+            SignMsg1, SignMsg4,
+        },
+        TssResult,
     };
 
+    const SESSION_ID: &[u8] = b"test session";
+
     pub fn verify_dalek(pk: &Point<Ed25519>, sig: &Signature, msg: &[u8]) -> bool {
         let mut sig_bytes = [0u8; 64];
         sig_bytes[..32].copy_from_slice(&*sig.R.to_bytes(true));
@@ -22,6 +49,86 @@ mod tests {
         dalek_pub.verify(msg, &dalek_sig).is_ok()
     }
 
+    fn keygen(t: u16, n: u16) -> Vec<SignKey> {
+        let (context1, msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+        let (context2, msgs2): (Vec<_>, Vec<_>) = context1
+            .into_iter()
+            .map(|context| keygen_phase2(context, msgs1.clone()).unwrap())
+            .unzip();
+        let (context3, msgs3): (Vec<_>, Vec<_>) = context2
+            .into_iter()
+            .map(|context| keygen_phase3(context, msgs2.clone()).unwrap())
+            .unzip();
+        context3
+            .into_iter()
+            .enumerate()
+            .map(|(index, context)| {
+                keygen_phase4(context, msgs3.iter().map(|m| m[index].clone()).collect()).unwrap()
+            })
+            .collect()
+    }
+
+    /// Runs signing phases 1-4 for `group` (1-based party indexes).
+    fn sign_to_phase4<F>(
+        sign_keys: &[SignKey],
+        group: &[u16],
+        phase1: F,
+    ) -> (Vec<SignContext4>, Vec<SignMsg4>)
+    where
+        F: Fn(&SignKey) -> TssResult<(SignContext1, SignMsg1)>,
+    {
+        let keys: Vec<_> = group
+            .iter()
+            .map(|&i| &sign_keys[usize::from(i - 1)])
+            .collect();
+
+        let (context1, msgs1): (Vec<_>, Vec<_>) =
+            keys.iter().map(|key| phase1(key).unwrap()).unzip();
+        let (context2, msgs2): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .zip(context1)
+            .map(|(key, context)| sign_phase2(key, context, msgs1.clone()).unwrap())
+            .unzip();
+        let (context3, msgs3): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .zip(context2)
+            .map(|(key, context)| sign_phase3(key, context, msgs2.clone()).unwrap())
+            .unzip();
+        keys.iter()
+            .zip(context3)
+            .enumerate()
+            .map(|(index, (key, context))| {
+                sign_phase4(
+                    key,
+                    context,
+                    msgs3.iter().map(|m| m[index].clone()).collect(),
+                )
+                .unwrap()
+            })
+            .unzip()
+    }
+
+    fn sign_with<F>(sign_keys: &[SignKey], group: &[u16], phase1: F) -> Vec<Signature>
+    where
+        F: Fn(&SignKey) -> TssResult<(SignContext1, SignMsg1)>,
+    {
+        let (context4, msgs4) = sign_to_phase4(sign_keys, group, phase1);
+        group
+            .iter()
+            .map(|&i| &sign_keys[usize::from(i - 1)])
+            .zip(context4)
+            .map(|(key, context)| sign_phase5(key, context, msgs4.clone()).unwrap())
+            .collect()
+    }
+
+    fn sign(sign_keys: &[SignKey], group: &[u16], message: &[u8]) -> Vec<Signature> {
+        sign_with(sign_keys, group, |key| {
+            sign_phase1(key, group.to_vec(), message, SESSION_ID)
+        })
+    }
+
     #[test]
     fn test_tss() {
         let t = 2;
@@ -29,7 +136,7 @@ mod tests {
 
         let (context1, msgs1): (Vec<_>, Vec<_>) = (0..n)
             .into_iter()
-            .map(|index| keygen_phase1(t, n, index + 1).unwrap())
+            .map(|index| keygen_phase1(t, n, index + 1, SESSION_ID).unwrap())
             .unzip();
         let (context2, msgs2): (Vec<_>, Vec<_>) = (0..n)
             .into_iter()
@@ -63,7 +170,10 @@ mod tests {
 
             let (sign_context1, sign_msg1): (Vec<_>, Vec<_>) = group_indexs
                 .iter()
-                .map(|&i| sign_phase1(&sign_keys[i as usize], group.clone(), &message).unwrap())
+                .map(|&i| {
+                    sign_phase1(&sign_keys[i as usize], group.clone(), &message, SESSION_ID)
+                        .unwrap()
+                })
                 .unzip();
 
             let (sign_context2, sign_msg2): (Vec<_>, Vec<_>) = group_indexs
@@ -138,7 +248,7 @@ mod tests {
 
         let (context1, msgs1): (Vec<_>, Vec<_>) = (0..n)
             .into_iter()
-            .map(|index| keygen_phase1(t, n, index + 1).unwrap())
+            .map(|index| keygen_phase1(t, n, index + 1, SESSION_ID).unwrap())
             .unzip();
         let (context2, msgs2): (Vec<_>, Vec<_>) = (0..n)
             .into_iter()
@@ -168,4 +278,800 @@ mod tests {
         let pk = base64::encode(&sign_keys[0].agg_pubkey.to_bytes(true).as_ref());
         println!("{}, {}, {}", ss.len(), vss.len(), pk.len())
     }
+
+    #[test]
+    fn test_address() {
+        let pk = Point::<Ed25519>::generator().to_point();
+        let address = pubkey_base58(&pk);
+        assert_eq!(address, "6x5SYnLroiN7WYq8NQYU9KHcH4YjpBbwpUfVu3EB7ieH");
+        assert!(pubkey_from_base58(&address).unwrap() == pk);
+    }
+
+    #[test]
+    fn test_refresh() {
+        let t = 1;
+        let n = 3;
+        let sign_keys = keygen(t, n);
+
+        let mut context1 = vec![];
+        let mut msgs1 = vec![];
+        let mut msgs2 = vec![];
+        for key in &sign_keys {
+            let (context, msg1, msg2) = refresh_phase1(key, SESSION_ID).unwrap();
+            context1.push(context);
+            msgs1.push(msg1);
+            msgs2.push(msg2);
+        }
+        let (context2, echoes): (Vec<_>, Vec<_>) = sign_keys
+            .iter()
+            .zip(context1.clone())
+            .enumerate()
+            .map(|(index, (key, context))| {
+                refresh_phase2(
+                    key,
+                    context,
+                    msgs1.clone(),
+                    msgs2.iter().map(|m| m[index].clone()).collect(),
+                )
+                .unwrap()
+            })
+            .unzip();
+        let refreshed: Vec<_> = sign_keys
+            .iter()
+            .zip(context2)
+            .map(|(key, context)| refresh_phase3(key, context, echoes.clone()).unwrap())
+            .collect();
+
+        for (old, new) in sign_keys.iter().zip(&refreshed) {
+            assert!(old.agg_pubkey == new.agg_pubkey);
+            assert!(old.secret_share.shared_keys.x_i != new.secret_share.shared_keys.x_i);
+        }
+
+        let message = b"refresh";
+        for group in (1u16..=n).combinations(usize::from(t + 1)) {
+            for sig in sign(&refreshed, &group, message) {
+                assert!(verify_dalek(&refreshed[0].agg_pubkey, &sig, message));
+            }
+        }
+
+        // messages of another refresh are rejected
+        let (_, other1, _) = refresh_phase1(&sign_keys[1], b"other session").unwrap();
+        let mut mixed = msgs1.clone();
+        mixed[1] = other1;
+        assert!(matches!(
+            refresh_phase2(
+                &sign_keys[0],
+                context1[0].clone(),
+                mixed,
+                msgs2.iter().map(|m| m[0].clone()).collect(),
+            ),
+            Err(TssError::WrongSession(2))
+        ));
+
+        // party 1 shows party 3 another zero sharing, consistent with its share
+        let (_, other1, other2) = refresh_phase1(&sign_keys[0], SESSION_ID).unwrap();
+        let mut seen1 = vec![msgs1.clone(), msgs1.clone(), msgs1];
+        seen1[2][0] = other1;
+        let mut seen2: Vec<Vec<_>> = (0..3)
+            .map(|index| msgs2.iter().map(|m| m[index].clone()).collect())
+            .collect();
+        seen2[2][0] = other2[2].clone();
+        let (context2, echoes): (Vec<_>, Vec<_>) = sign_keys
+            .iter()
+            .zip(context1)
+            .zip(seen1.into_iter().zip(seen2))
+            .map(|((key, context), (msgs1, msgs2))| {
+                refresh_phase2(key, context, msgs1, msgs2).unwrap()
+            })
+            .unzip();
+        for (key, context) in sign_keys.iter().zip(context2) {
+            assert!(matches!(
+                refresh_phase3(key, context, echoes.clone()),
+                Err(TssError::Equivocation { sender: 1, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reshare() {
+        let sign_keys = keygen(1, 3);
+        let old_parties = vec![1u16, 3];
+        let (t, n) = (2, 4);
+
+        let (msgs1, msgs2): (Vec<_>, Vec<_>) = old_parties
+            .iter()
+            .map(|&i| {
+                let key = &sign_keys[usize::from(i - 1)];
+                reshare_phase1(key, old_parties.clone(), t, n, SESSION_ID).unwrap()
+            })
+            .unzip();
+        let received = |j: u16| -> Vec<_> {
+            msgs2
+                .iter()
+                .map(|m| m[usize::from(j - 1)].clone())
+                .collect()
+        };
+        let (context2, echoes): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|j| reshare_phase2(t, n, j, SESSION_ID, msgs1.clone(), received(j)).unwrap())
+            .unzip();
+        let reshared: Vec<_> = context2
+            .into_iter()
+            .map(|context| reshare_phase3(context, echoes.clone()).unwrap())
+            .collect();
+
+        let message = b"reshare";
+        for key in &reshared {
+            assert!(key.agg_pubkey == sign_keys[0].agg_pubkey);
+        }
+        for group in (1u16..=n).combinations(usize::from(t + 1)) {
+            for sig in sign(&reshared, &group, message) {
+                assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+            }
+        }
+
+        let mut tampered = received(1);
+        tampered[1].secret_share = tampered[0].secret_share.clone();
+        assert!(reshare_phase2(t, n, 1, SESSION_ID, msgs1.clone(), tampered).is_err());
+
+        let mut truncated = msgs1.clone();
+        for msg in &mut truncated {
+            for vss in &mut msg.old_vss_schemes {
+                vss.commitments.clear();
+            }
+        }
+        assert!(reshare_phase2(t, n, 1, SESSION_ID, truncated, received(1)).is_err());
+
+        assert!(matches!(
+            reshare_phase2(t, n, 1, b"other session", msgs1.clone(), received(1)),
+            Err(TssError::WrongSession(1))
+        ));
+
+        // old party 1 shows new party 4 another dealing, consistent with its share
+        let (other1, other2) =
+            reshare_phase1(&sign_keys[0], old_parties.clone(), t, n, SESSION_ID).unwrap();
+        let (context2, echoes): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|j| {
+                let (mut seen1, mut seen2) = (msgs1.clone(), received(j));
+                if j == 4 {
+                    seen1[0] = other1.clone();
+                    seen2[0] = other2[3].clone();
+                }
+                reshare_phase2(t, n, j, SESSION_ID, seen1, seen2).unwrap()
+            })
+            .unzip();
+        for context in context2 {
+            assert!(matches!(
+                reshare_phase3(context, echoes.clone()),
+                Err(TssError::Equivocation { sender: 1, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_recover() {
+        let mut sign_keys = keygen(1, 3);
+        let helpers = vec![1u16, 3];
+        let lost_index = 2;
+
+        let (context1, msgs1): (Vec<_>, Vec<_>) = helpers
+            .iter()
+            .map(|&i| {
+                recover_phase1(&sign_keys[usize::from(i - 1)], helpers.clone(), lost_index).unwrap()
+            })
+            .unzip();
+        let msgs2: Vec<_> = helpers
+            .iter()
+            .zip(context1)
+            .map(|(&i, context)| {
+                let received = msgs1.iter().flatten().filter(|m| m.receiver == i).cloned();
+                recover_phase2(&sign_keys[usize::from(i - 1)], context, received.collect()).unwrap()
+            })
+            .collect();
+        let recovered = recover_phase3(lost_index, msgs2.clone()).unwrap();
+
+        assert!(
+            recovered.secret_share.shared_keys.x_i
+                == sign_keys[usize::from(lost_index - 1)]
+                    .secret_share
+                    .shared_keys
+                    .x_i
+        );
+        sign_keys[usize::from(lost_index - 1)] = recovered;
+
+        let message = b"recover";
+        for group in [vec![1u16, 2], vec![2, 3]] {
+            for sig in sign(&sign_keys, &group, message) {
+                assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+            }
+        }
+
+        assert!(recover_phase3(lost_index, msgs2[..1].to_vec()).is_err());
+
+        let mut truncated = msgs2.clone();
+        for msg in &mut truncated {
+            for vss in &mut msg.vss_schemes {
+                vss.commitments.clear();
+            }
+        }
+        assert!(recover_phase3(lost_index, truncated).is_err());
+    }
+
+    #[test]
+    fn test_hedged_nonce() {
+        let sign_keys = keygen(1, 3);
+        let message = b"nonce";
+
+        let (context_a, _) = sign_phase1(&sign_keys[0], vec![1, 2], message, SESSION_ID).unwrap();
+        let (context_b, _) = sign_phase1(&sign_keys[0], vec![1, 3], message, SESSION_ID).unwrap();
+        assert!(context_a.nonce_key.R_i != context_b.nonce_key.R_i);
+
+        let (context_a, _) =
+            sign_phase1_deterministic(&sign_keys[0], vec![1, 2], message, SESSION_ID).unwrap();
+        let (context_b, _) =
+            sign_phase1_deterministic(&sign_keys[0], vec![1, 3], message, SESSION_ID).unwrap();
+        assert!(context_a.nonce_key.R_i == context_b.nonce_key.R_i);
+    }
+
+    #[test]
+    fn test_identifiable_abort() {
+        let sign_keys = keygen(2, 4);
+        let group = [1, 2, 4];
+        let message = b"abort";
+
+        let (context4, mut msgs4) = sign_to_phase4(&sign_keys, &group, |key| {
+            sign_phase1(key, group.to_vec(), message, SESSION_ID)
+        });
+        let partial_sig = msgs4[1].partial_sig.clone();
+        msgs4[1].partial_sig = msgs4[2].partial_sig.clone();
+        msgs4[2].partial_sig = partial_sig;
+
+        match sign_phase5(&sign_keys[0], context4[0].clone(), msgs4) {
+            Err(TssError::InvalidPartialSignatures(culprits)) => assert_eq!(culprits, vec![2, 4]),
+            _ => panic!("expected invalid partial signatures"),
+        }
+    }
+
+    #[test]
+    fn test_keygen_complaint() {
+        let (t, n) = (1, 4);
+        let (context1, msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+        let (context2, msgs2): (Vec<_>, Vec<_>) = context1
+            .into_iter()
+            .map(|context| keygen_phase2(context, msgs1.clone()).unwrap())
+            .unzip();
+        let (context3, sent_msgs3): (Vec<_>, Vec<_>) = context2
+            .into_iter()
+            .map(|context| keygen_phase3(context, msgs2.clone()).unwrap())
+            .unzip();
+
+        // party 3 deals a bad share to party 1
+        let mut msgs3 = sent_msgs3.clone();
+        msgs3[2][0].secret_share = Scalar::random();
+        let received =
+            |index: usize| -> Vec<_> { msgs3.iter().map(|m| m[index].clone()).collect() };
+
+        match keygen_phase4(context3[0].clone(), received(0)) {
+            Err(TssError::InvalidKeyGenShares(culprits)) => assert_eq!(culprits, vec![3]),
+            _ => panic!("expected invalid keygen shares"),
+        }
+
+        let complaints: Vec<_> = (0..context3.len())
+            .map(|index| keygen_complaint_phase1(&context3[index], &received(index)).unwrap())
+            .collect();
+        assert_eq!(complaints[0].accused, vec![3]);
+        assert!(complaints[1..].iter().all(|msg| msg.accused.is_empty()));
+
+        // an honest reveal settles the complaint
+        let responses: Vec<_> = (0..context3.len())
+            .map(|index| {
+                keygen_complaint_phase2(&context3[index], &sent_msgs3[index], &complaints).unwrap()
+            })
+            .collect();
+        let sign_keys: Vec<_> = (0..context3.len())
+            .map(|index| {
+                keygen_complaint_phase3(
+                    context3[index].clone(),
+                    received(index),
+                    complaints.clone(),
+                    responses.clone(),
+                )
+                .unwrap()
+            })
+            .collect();
+        let message = b"complaint";
+        for sig in sign(&sign_keys, &[1, 3], message) {
+            assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+        }
+
+        // revealing the bad share gets party 3 disqualified everywhere
+        let responses: Vec<_> = (0..context3.len())
+            .map(|index| {
+                keygen_complaint_phase2(&context3[index], &msgs3[index], &complaints).unwrap()
+            })
+            .collect();
+        for index in 0..context3.len() {
+            match keygen_complaint_phase3(
+                context3[index].clone(),
+                received(index),
+                complaints.clone(),
+                responses.clone(),
+            ) {
+                Err(TssError::KeyGenDisqualified(parties)) => assert_eq!(parties, vec![3]),
+                _ => panic!("expected party 3 to be disqualified"),
+            }
+        }
+
+        // so does not answering at all
+        let mut silent = responses.clone();
+        silent.remove(2);
+        for index in 0..context3.len() {
+            match keygen_complaint_phase3(
+                context3[index].clone(),
+                received(index),
+                complaints.clone(),
+                silent.clone(),
+            ) {
+                Err(TssError::KeyGenDisqualified(parties)) => assert_eq!(parties, vec![3]),
+                _ => panic!("expected party 3 to be disqualified"),
+            }
+        }
+
+        // party 2 shows party 1 other commitments, consistent with its share
+        let (zero_vss, zero_shares) = VerifiableSS::<Ed25519>::share(t, n, &Scalar::zero());
+        let mut msgs3 = sent_msgs3.clone();
+        let msg = &mut msgs3[1][0];
+        msg.secret_share = &msg.secret_share + &zero_shares[0];
+        for (commitment, zero) in msg
+            .vss_scheme
+            .commitments
+            .iter_mut()
+            .zip(&zero_vss.commitments)
+        {
+            *commitment = &*commitment + zero;
+        }
+        let received =
+            |index: usize| -> Vec<_> { msgs3.iter().map(|m| m[index].clone()).collect() };
+        keygen_phase4(context3[0].clone(), received(0)).unwrap();
+
+        let complaints: Vec<_> = (0..context3.len())
+            .map(|index| keygen_complaint_phase1(&context3[index], &received(index)).unwrap())
+            .collect();
+        assert!(complaints.iter().all(|msg| msg.accused.is_empty()));
+        let responses: Vec<_> = (0..context3.len())
+            .map(|index| {
+                keygen_complaint_phase2(&context3[index], &sent_msgs3[index], &complaints).unwrap()
+            })
+            .collect();
+        for index in 0..context3.len() {
+            match keygen_complaint_phase3(
+                context3[index].clone(),
+                received(index),
+                complaints.clone(),
+                responses.clone(),
+            ) {
+                Err(TssError::KeyGenDisqualified(parties)) => assert_eq!(parties, vec![2]),
+                _ => panic!("expected party 2 to be disqualified"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_message_validation() {
+        assert!(matches!(
+            keygen_phase1(2, 2, 1, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+        assert!(matches!(
+            keygen_phase1(1, 3, 0, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+        assert!(matches!(
+            keygen_phase1(1, 3, 4, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+
+        let (t, n) = (1, 3);
+        let (context1, msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+
+        let mut duplicated = msgs1.clone();
+        duplicated[2].sender = 2;
+        assert!(matches!(
+            keygen_phase2(context1[0].clone(), duplicated),
+            Err(TssError::DuplicateSender(2))
+        ));
+        let mut unknown = msgs1.clone();
+        unknown[2].sender = 4;
+        assert!(matches!(
+            keygen_phase2(context1[0].clone(), unknown),
+            Err(TssError::UnknownSender(4))
+        ));
+
+        let (context2, msgs2): (Vec<_>, Vec<_>) = context1
+            .into_iter()
+            .map(|context| keygen_phase2(context, msgs1.clone()).unwrap())
+            .unzip();
+        let (context3, msgs3): (Vec<_>, Vec<_>) = context2
+            .into_iter()
+            .map(|context| keygen_phase3(context, msgs2.clone()).unwrap())
+            .unzip();
+        // party 1 is handed the messages meant for party 2
+        assert!(matches!(
+            keygen_phase4(
+                context3[0].clone(),
+                msgs3.iter().map(|m| m[1].clone()).collect()
+            ),
+            Err(TssError::WrongReceiver(1))
+        ));
+
+        let sign_keys = keygen(t, n);
+        let message = b"validation";
+        assert!(matches!(
+            sign_phase1(&sign_keys[0], vec![2, 3], message, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+        assert!(matches!(
+            sign_phase1(&sign_keys[0], vec![1, 4], message, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+        assert!(matches!(
+            sign_phase1(&sign_keys[0], vec![1, 1], message, SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+    }
+
+    #[test]
+    fn test_channel() {
+        let (t, n) = (1, 3);
+        let identities: Vec<_> = (0..n).map(|_| IdentityKey::create()).collect();
+        let (context1, msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+
+        let signed = sign_broadcast(&identities[0], 1, b"keygen 1", &msgs1[0]).unwrap();
+        let opened: KeyGenMsg1 =
+            open_broadcast(&identities[0].public, b"keygen 1", &signed).unwrap();
+        assert_eq!(opened.sender, 1);
+        assert!(matches!(
+            open_broadcast::<KeyGenMsg1>(&identities[1].public, b"keygen 1", &signed),
+            Err(TssError::AuthenticationFailed(1))
+        ));
+        // replayed into another round
+        assert!(matches!(
+            open_broadcast::<KeyGenMsg1>(&identities[0].public, b"keygen 2", &signed),
+            Err(TssError::AuthenticationFailed(1))
+        ));
+        assert!(open_broadcast::<KeyGenMsg1>(&Point::zero(), b"keygen 1", &signed).is_err());
+        // party 2 passes off party 1's message as its own
+        let signed = sign_broadcast(&identities[1], 2, b"keygen 1", &msgs1[0]).unwrap();
+        assert!(matches!(
+            open_broadcast::<KeyGenMsg1>(&identities[1].public, b"keygen 1", &signed),
+            Err(TssError::AuthenticationFailed(2))
+        ));
+
+        let (context2, msgs2): (Vec<_>, Vec<_>) = context1
+            .into_iter()
+            .map(|context| keygen_phase2(context, msgs1.clone()).unwrap())
+            .unzip();
+        let (_, msgs3): (Vec<_>, Vec<_>) = context2
+            .into_iter()
+            .map(|context| keygen_phase3(context, msgs2.clone()).unwrap())
+            .unzip();
+
+        let msg = &msgs3[0][1];
+        let label = b"keygen 3";
+        let mut sealed = seal(&identities[0], 1, 2, &identities[1].public, label, msg).unwrap();
+        let opened: KeyGenMsg3 =
+            open(&identities[1], 2, &identities[0].public, label, &sealed).unwrap();
+        assert!(opened.secret_share == msg.secret_share);
+
+        // the payload must be from the sealing party and to the opening one
+        for forged in [&msgs3[0][2], &msgs3[2][1]] {
+            let sealed = seal(&identities[0], 1, 2, &identities[1].public, label, forged).unwrap();
+            assert!(matches!(
+                open::<KeyGenMsg3>(&identities[1], 2, &identities[0].public, label, &sealed),
+                Err(TssError::AuthenticationFailed(1))
+            ));
+        }
+
+        // a third party can neither read nor forge it
+        assert!(
+            open::<KeyGenMsg3>(&identities[2], 2, &identities[0].public, label, &sealed).is_err()
+        );
+        assert!(matches!(
+            open::<KeyGenMsg3>(&identities[1], 3, &identities[0].public, label, &sealed),
+            Err(TssError::WrongReceiver(1))
+        ));
+        assert!(matches!(
+            open::<KeyGenMsg3>(
+                &identities[1],
+                2,
+                &identities[0].public,
+                b"keygen 4",
+                &sealed
+            ),
+            Err(TssError::AuthenticationFailed(1))
+        ));
+        assert!(seal(&identities[0], 1, 2, &Point::zero(), label, msg).is_err());
+        sealed.ciphertext[0] ^= 1;
+        assert!(matches!(
+            open::<KeyGenMsg3>(&identities[1], 2, &identities[0].public, label, &sealed),
+            Err(TssError::AuthenticationFailed(1))
+        ));
+    }
+
+    #[test]
+    fn test_echo() {
+        let (t, n) = (1, 3);
+        let (_, msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+
+        let mut echoes: Vec<_> = (1..=n)
+            .map(|index| echo_phase1(index, &msgs1).unwrap())
+            .collect();
+        let parties: Vec<_> = (1..=n).collect();
+        echo_phase2(&parties, &msgs1, &echoes).unwrap();
+
+        // a party dropped from both the messages and the echoes is noticed
+        assert!(matches!(
+            echo_phase2(&parties, &msgs1[..2], &echoes[..2]),
+            Err(TssError::InputsLengthUnmatch)
+        ));
+
+        // party 2 shows party 3 a different commitment
+        let (_, other) = keygen_phase1(t, n, 2, SESSION_ID).unwrap();
+        let mut seen_by_3 = msgs1.clone();
+        seen_by_3[1] = other;
+        echoes[2] = echo_phase1(3, &seen_by_3).unwrap();
+        assert!(matches!(
+            echo_phase2(&parties, &msgs1, &echoes),
+            Err(TssError::Equivocation {
+                sender: 2,
+                echoed_by: 3
+            })
+        ));
+        assert!(matches!(
+            echo_phase2(&parties, &seen_by_3, &echoes),
+            Err(TssError::Equivocation {
+                sender: 2,
+                echoed_by: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_session() {
+        let (t, n) = (1, 3);
+        let (context1, mut msgs1): (Vec<_>, Vec<_>) = (1..=n)
+            .map(|index| keygen_phase1(t, n, index, SESSION_ID).unwrap())
+            .unzip();
+        msgs1[2] = keygen_phase1(t, n, 3, b"other session").unwrap().1;
+        assert!(matches!(
+            keygen_phase2(context1[0].clone(), msgs1),
+            Err(TssError::WrongSession(3))
+        ));
+
+        // two signing sessions for the same key and signers, run concurrently
+        let sign_keys = keygen(t, n);
+        let group = vec![1, 2];
+        let (context_a, msg_a) =
+            sign_phase1(&sign_keys[0], group.clone(), b"message a", SESSION_ID).unwrap();
+        let (_, msg_b) =
+            sign_phase1(&sign_keys[1], group.clone(), b"message b", SESSION_ID).unwrap();
+        assert!(matches!(
+            sign_phase2(&sign_keys[0], context_a, vec![msg_a, msg_b]),
+            Err(TssError::WrongSession(2))
+        ));
+    }
+
+    #[test]
+    fn test_frost() {
+        let sign_keys = keygen(2, 4);
+        let group = [1u16, 3, 4];
+        let keys: Vec<_> = group
+            .iter()
+            .map(|&i| &sign_keys[usize::from(i - 1)])
+            .collect();
+
+        // preprocessing: two nonces per signer
+        let (mut contexts1, msgs1): (Vec<_>, Vec<_>) =
+            keys.iter().map(|key| frost_phase1(key, 2).unwrap()).unzip();
+
+        for (batch, message) in [&b"first"[..], &b"second"[..]].into_iter().enumerate() {
+            let commitments: Vec<_> = msgs1
+                .iter()
+                .map(|msg| msg.commitments[batch].clone())
+                .collect();
+            let mut msgs2: Vec<_> = keys
+                .iter()
+                .zip(contexts1.iter_mut())
+                .map(|(key, context)| {
+                    frost_phase2(key, context, message, commitments.clone()).unwrap()
+                })
+                .collect();
+            let sig =
+                frost_phase3(&sign_keys[0], message, commitments.clone(), msgs2.clone()).unwrap();
+            assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+
+            // nonces are single use
+            assert!(
+                frost_phase2(keys[0], &mut contexts1[0], message, commitments.clone()).is_err()
+            );
+
+            msgs2[1].sig_share = msgs2[1].sig_share.clone() + Scalar::from(1u16);
+            match frost_phase3(&sign_keys[0], message, commitments, msgs2) {
+                Err(TssError::InvalidPartialSignatures(culprits)) => assert_eq!(culprits, vec![3]),
+                _ => panic!("expected invalid partial signatures"),
+            }
+        }
+
+        // identity commitments are refused before our nonce is spent
+        let (mut context1, msgs1): (Vec<_>, Vec<_>) =
+            keys.iter().map(|key| frost_phase1(key, 1).unwrap()).unzip();
+        let mut commitments: Vec<_> = msgs1.iter().map(|msg| msg.commitments[0].clone()).collect();
+        commitments[1].binding = Point::zero();
+        assert!(frost_phase2(keys[0], &mut context1[0], b"first", commitments).is_err());
+        assert_eq!(context1[0].nonces.len(), 1);
+    }
+
+    #[test]
+    fn test_ed25519_variants() {
+        let sign_keys = keygen(1, 3);
+        let group = [1, 3];
+        let pubkey = &sign_keys[0].agg_pubkey;
+        let data = vec![0x5au8; 1 << 20];
+        let context = b"firmware";
+
+        let mut digest = [0u8; 64];
+        digest.copy_from_slice(&Sha512::digest(&data));
+        let dalek_pub = ed25519_dalek::PublicKey::from_bytes(&*pubkey.to_bytes(true)).unwrap();
+        for sig in sign_with(&sign_keys, &group, |key| {
+            sign_phase1_ph(key, group.to_vec(), &digest, context, SESSION_ID)
+        }) {
+            let mut sig_bytes = [0u8; 64];
+            sig_bytes[..32].copy_from_slice(&*sig.R.to_bytes(true));
+            sig_bytes[32..].copy_from_slice(&sig.s.to_bytes());
+            let dalek_sig = ed25519_dalek::Signature::from_bytes(&sig_bytes).unwrap();
+            assert!(dalek_pub
+                .verify_prehashed(Sha512::new().chain(&data), Some(&context[..]), &dalek_sig)
+                .is_ok());
+            assert!(!verify_dalek(pubkey, &sig, &digest));
+        }
+
+        let variant = Ed25519Variant::Ctx(context.to_vec());
+        for sig in sign_with(&sign_keys, &group, |key| {
+            sign_phase1_ctx(key, group.to_vec(), &data, context, SESSION_ID)
+        }) {
+            variant.verify(&sig, &data, pubkey).unwrap();
+            assert!(!verify_dalek(pubkey, &sig, &data));
+        }
+        assert!(matches!(
+            sign_phase1_ctx(&sign_keys[0], group.to_vec(), &data, b"", SESSION_ID),
+            Err(TssError::InvalidParameters)
+        ));
+
+        // RFC 8032 section 7.2, Ed25519ctx with context "foo"
+        let seed = hex::decode("0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6")
+            .unwrap();
+        let public =
+            hex::decode("dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292")
+                .unwrap();
+        let message = hex::decode("f726936d19c800494e3fdaff20b276a8").unwrap();
+        let expected = hex::decode(
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+             8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d",
+        )
+        .unwrap();
+        let scalar_le = |bytes: &[u8]| {
+            let mut bytes = bytes.to_vec();
+            bytes.reverse();
+            Scalar::<Ed25519>::from_bigint(&BigInt::from_bytes(&bytes))
+        };
+
+        let h = Sha512::digest(&seed);
+        let mut a = [0u8; 32];
+        a.copy_from_slice(&h[..32]);
+        a[0] &= 248;
+        a[31] &= 127;
+        a[31] |= 64;
+        let a = scalar_le(&a);
+        let pubkey = Point::<Ed25519>::generator() * &a;
+        assert_eq!(&*pubkey.to_bytes(true), &public[..]);
+
+        let r = scalar_le(
+            &Sha512::new()
+                .chain(b"SigEd25519 no Ed25519 collisions\x00\x03foo")
+                .chain(&h[32..])
+                .chain(&message)
+                .finalize(),
+        );
+        let nonce = Point::<Ed25519>::generator() * &r;
+        assert_eq!(&*nonce.to_bytes(true), &expected[..32]);
+
+        let variant = Ed25519Variant::Ctx(b"foo".to_vec());
+        let s = &r + variant.challenge(&nonce, &pubkey, &message) * &a;
+        assert_eq!(&s.to_bytes()[..], &expected[32..]);
+
+        let sig = Signature {
+            R: Point::from_bytes(&expected[..32]).unwrap(),
+            s: scalar_le(&expected[32..]),
+        };
+        variant.verify(&sig, &message, &pubkey).unwrap();
+        assert!(Ed25519Variant::Ctx(b"bar".to_vec())
+            .verify(&sig, &message, &pubkey)
+            .is_err());
+        assert!(Ed25519Variant::Pure
+            .verify(&sig, &message, &pubkey)
+            .is_err());
+    }
+
+    #[test]
+    fn test_derive() {
+        let sign_keys = keygen(1, 3);
+        let chain_code: [u8; 32] = thread_rng().gen();
+        let path = [0, 7];
+
+        let (child_pubkey, child_chain_code) =
+            derive_pubkey(&sign_keys[0].agg_pubkey, &chain_code, &path).unwrap();
+        assert!(child_pubkey != sign_keys[0].agg_pubkey);
+        let child_keys: Vec<_> = sign_keys
+            .iter()
+            .map(|key| {
+                let (child, code) = derive_sign_key(key, &chain_code, &path).unwrap();
+                assert_eq!(code, child_chain_code);
+                assert!(child.agg_pubkey == child_pubkey);
+                child
+            })
+            .collect();
+
+        let message = b"deposit";
+        for sig in sign(&child_keys, &[2, 3], message) {
+            assert!(verify_dalek(&child_pubkey, &sig, message));
+        }
+
+        // one step at a time gives the same key
+        let (step_pubkey, step_chain_code) =
+            derive_pubkey(&sign_keys[0].agg_pubkey, &chain_code, &path[..1]).unwrap();
+        let (step_pubkey, _) = derive_pubkey(&step_pubkey, &step_chain_code, &path[1..]).unwrap();
+        assert!(step_pubkey == child_pubkey);
+
+        assert!(matches!(
+            derive_pubkey(&sign_keys[0].agg_pubkey, &chain_code, &[1 << 31]),
+            Err(TssError::InvalidParameters)
+        ));
+    }
+
+    #[test]
+    fn test_dealer() {
+        let seed: [u8; 32] = thread_rng().gen();
+        let secret = ed25519_dalek::SecretKey::from_bytes(&seed).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        let sign_keys = dealer_split_seed(&seed, 1, 3).unwrap();
+        for key in &sign_keys {
+            assert_eq!(&*key.agg_pubkey.to_bytes(true), public.as_bytes());
+        }
+        let message = b"migrate";
+        for group in (1u16..=3).combinations(2) {
+            for sig in sign(&sign_keys, &group, message) {
+                assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+            }
+        }
+
+        let expanded = ed25519_dalek::ExpandedSecretKey::from(&secret).to_bytes();
+        let sign_keys = dealer_split_expanded(&expanded, 2, 4).unwrap();
+        assert_eq!(&*sign_keys[0].agg_pubkey.to_bytes(true), public.as_bytes());
+        for sig in sign(&sign_keys, &[1, 2, 4], message) {
+            assert!(verify_dalek(&sign_keys[0].agg_pubkey, &sig, message));
+        }
+
+        assert!(matches!(
+            dealer_split_seed(&seed, 3, 3),
+            Err(TssError::InvalidParameters)
+        ));
+    }
 }
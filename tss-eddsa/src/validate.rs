@@ -0,0 +1,95 @@
+use multi_party_eddsa::protocols::thresholdsig::Parameters;
+use sha2::{Digest, Sha256};
+
+use crate::{error::TssError, TssResult};
+
+/// `t < n`, and `party_index` is one of `1..=n`.
+pub(crate) fn check_params(t: u16, n: u16, party_index: u16) -> TssResult<()> {
+    if t >= n || party_index == 0 || party_index > n {
+        return Err(TssError::InvalidParameters);
+    }
+    Ok(())
+}
+
+/// `parties` are sorted, distinct, valid indexes for `params`, more than `t`
+/// of them, and include `party_index`.
+pub(crate) fn check_parties(
+    params: &Parameters,
+    parties: &[u16],
+    party_index: u16,
+) -> TssResult<()> {
+    if parties.len() <= usize::from(params.threshold)
+        || parties.windows(2).any(|w| w[0] >= w[1])
+        || parties.iter().any(|&i| i == 0 || i > params.share_count)
+        || !parties.contains(&party_index)
+    {
+        return Err(TssError::InvalidParameters);
+    }
+    Ok(())
+}
+
+/// Exactly one message from each of `expected`.
+pub(crate) fn check_senders<I>(senders: I, expected: &[u16]) -> TssResult<()>
+where
+    I: IntoIterator<Item = u16>,
+{
+    if check_known_senders(senders, expected)? != expected.len() {
+        return Err(TssError::InputsLengthUnmatch);
+    }
+    Ok(())
+}
+
+/// At most one message from each of `expected`, returns how many there are.
+pub(crate) fn check_known_senders<I>(senders: I, expected: &[u16]) -> TssResult<usize>
+where
+    I: IntoIterator<Item = u16>,
+{
+    let mut seen = vec![];
+    for sender in senders {
+        if !expected.contains(&sender) {
+            return Err(TssError::UnknownSender(sender));
+        }
+        if seen.contains(&sender) {
+            return Err(TssError::DuplicateSender(sender));
+        }
+        seen.push(sender);
+    }
+    Ok(seen.len())
+}
+
+/// Every `(sender, receiver)` pair is addressed to `party_index`.
+pub(crate) fn check_receivers<I>(messages: I, party_index: u16) -> TssResult<()>
+where
+    I: IntoIterator<Item = (u16, u16)>,
+{
+    match messages
+        .into_iter()
+        .find(|&(_, receiver)| receiver != party_index)
+    {
+        Some((sender, _)) => Err(TssError::WrongReceiver(sender)),
+        None => Ok(()),
+    }
+}
+
+/// Tag of a protocol session: `domain`, then `session_id` and each of
+/// `fields` length-prefixed.
+pub(crate) fn session_tag(domain: &[u8], session_id: &[u8], fields: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    for field in [session_id].iter().chain(fields) {
+        hasher.update((field.len() as u64).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.finalize().into()
+}
+
+/// Every `(sender, session)` pair belongs to `session`.
+pub(crate) fn check_session<I>(messages: I, session: &[u8; 32]) -> TssResult<()>
+where
+    I: IntoIterator<Item = (u16, [u8; 32])>,
+{
+    match messages.into_iter().find(|(_, tag)| tag != session) {
+        Some((sender, _)) => Err(TssError::WrongSession(sender)),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,47 @@
+//! The subset of `lindell::error::LindellError` raised by the shared
+//! `modulus` module; it reaches JS as a `JsError`.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LindellError {
+    PaillierModulusTooSmall(usize),
+    PaillierModulusTooLarge(usize),
+    InvalidPaillierBlumProof,
+    InvalidNoSmallFactorProof,
+    InvalidRingPedersenProof,
+    RingPedersenModulusTooSmall(usize),
+    RingPedersenModulusTooLarge(usize),
+    SerdeJsonError(serde_json::Error),
+}
+
+impl fmt::Display for LindellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PaillierModulusTooSmall(bits) => {
+                write!(f, "paillier modulus too small: {} bits", bits)
+            }
+            Self::PaillierModulusTooLarge(bits) => {
+                write!(f, "paillier modulus too large: {} bits", bits)
+            }
+            Self::InvalidPaillierBlumProof => write!(f, "invalid paillier-blum modulus proof"),
+            Self::InvalidNoSmallFactorProof => write!(f, "invalid no small factor proof"),
+            Self::InvalidRingPedersenProof => write!(f, "invalid ring-pedersen parameters proof"),
+            Self::RingPedersenModulusTooSmall(bits) => {
+                write!(f, "ring-pedersen modulus too small: {} bits", bits)
+            }
+            Self::RingPedersenModulusTooLarge(bits) => {
+                write!(f, "ring-pedersen modulus too large: {} bits", bits)
+            }
+            Self::SerdeJsonError(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LindellError {}
+
+impl From<serde_json::Error> for LindellError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::SerdeJsonError(err)
+    }
+}
@@ -10,10 +10,15 @@ use multi_party_ecdsa::{
 use paillier::EncryptionKey;
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
-use zk_paillier::zkproofs::{CompositeDLogProof, NiCorrectKeyProof};
+use zk_paillier::zkproofs::{CompositeDLogProof, DLogStatement, NiCorrectKeyProof};
 
 use super::{
-    deserialize_paillier_public, deserialize_point, serialize_paillier_public, serialize_point,
+    deserialize_paillier_public, deserialize_point,
+    modulus::{
+        li17_p1_paillier_key_pair, ring_pedersen_setup, verify_paillier_modulus,
+        verify_ring_pedersen, NoSmallFactorProof, PaillierBlumModulusProof, RingPedersenProof,
+    },
+    serialize_paillier_public, serialize_point,
 };
 
 // party one structures
@@ -41,6 +46,8 @@ pub type Li17KeyGenP1Msg2 = (
     CompositeDLogProof,
     EncryptionKey,
     BigInt,
+    PaillierBlumModulusProof,
+    NoSmallFactorProof,
 );
 
 //party two structures
@@ -48,9 +55,17 @@ pub type Li17KeyGenP1Msg2 = (
 pub struct Li17KeyGenP2Context1 {
     p2_msg1_from_p1: party_one::KeyGenFirstMsg,
     p2_ec_key_pair: party_two::EcKeyPair,
+    ring_pedersen: DLogStatement,
 }
 
-pub type Li17KeyGenP2Msg1 = party_two::KeyGenFirstMsg;
+/// P2's key share message, plus the Ring-Pedersen parameters P1 proves
+/// its Paillier modulus against.
+#[derive(Serialize, Deserialize)]
+pub struct Li17KeyGenP2Msg1 {
+    pub first_msg: party_two::KeyGenFirstMsg,
+    pub ring_pedersen: DLogStatement,
+    pub ring_pedersen_proof: RingPedersenProof,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct Li17SignP2Context {
@@ -96,11 +111,13 @@ pub fn li17_p1_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
     let context: Li17KeyGenP1Context1 = context.into_serde()?;
     let msg: Li17KeyGenP2Msg1 = msg.into_serde()?;
 
-    let p1_second_message =
-        party_one::KeyGenSecondMsg::verify_and_decommit(context.p1_comm_witness, &msg.d_log_proof)?;
+    verify_ring_pedersen(&msg.ring_pedersen, &msg.ring_pedersen_proof)?;
+    let p1_second_message = party_one::KeyGenSecondMsg::verify_and_decommit(
+        context.p1_comm_witness,
+        &msg.first_msg.d_log_proof,
+    )?;
 
-    let paillier_key_pair =
-        party_one::PaillierKeyPair::generate_keypair_and_encrypted_share(&context.p1_ec_key_pair);
+    let (paillier_key_pair, paillier_dk) = li17_p1_paillier_key_pair(&context.p1_ec_key_pair)?;
     let party_one_private =
         party_one::Party1Private::set_private_key(&context.p1_ec_key_pair, &paillier_key_pair);
 
@@ -110,15 +127,17 @@ pub fn li17_p1_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
     let (pdl_statement, pdl_proof, composite_dlog_proof) =
         party_one::PaillierKeyPair::pdl_proof(&party_one_private, &paillier_key_pair);
     let ek = paillier_key_pair.ek.clone();
+    let blum_proof = PaillierBlumModulusProof::prove(&ek, &paillier_dk)?;
+    let no_small_factor_proof = NoSmallFactorProof::prove(&ek, &paillier_dk, &msg.ring_pedersen)?;
     let encrypted_share = paillier_key_pair.encrypted_share.clone();
 
     let party_one_private =
         party_one::Party1Private::set_private_key(&context.p1_ec_key_pair, &paillier_key_pair);
-    let public_key = party_one::compute_pubkey(&party_one_private, &msg.public_share);
+    let public_key = party_one::compute_pubkey(&party_one_private, &msg.first_msg.public_share);
     let sign_context = Li17SignP1Context {
         public: public_key.clone(),
         public_p1: context.p1_ec_key_pair.public_share,
-        public_p2: msg.public_share,
+        public_p2: msg.first_msg.public_share,
         p1_private: party_one_private,
     };
     let p1_msg2_for_p2 = (
@@ -129,6 +148,8 @@ pub fn li17_p1_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
         composite_dlog_proof,
         ek,
         encrypted_share,
+        blum_proof,
+        no_small_factor_proof,
     );
     Ok(JsValue::from_serde(&(sign_context, p1_msg2_for_p2))?)
 }
@@ -138,11 +159,18 @@ pub fn li17_p1_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
 pub fn li17_p2_key_gen1(message: JsValue) -> Result<JsValue, JsError> {
     let msg: Li17KeyGenP1Msg1 = message.into_serde()?;
     let (p2_first_message, p2_ec_key_pair) = party_two::KeyGenFirstMsg::create();
+    let (ring_pedersen, ring_pedersen_proof) = ring_pedersen_setup();
     let context2 = Li17KeyGenP2Context1 {
         p2_msg1_from_p1: msg,
         p2_ec_key_pair,
+        ring_pedersen: ring_pedersen.clone(),
+    };
+    let p2_msg1 = Li17KeyGenP2Msg1 {
+        first_msg: p2_first_message,
+        ring_pedersen,
+        ring_pedersen_proof,
     };
-    Ok(JsValue::from_serde(&(context2, p2_first_message))?)
+    Ok(JsValue::from_serde(&(context2, p2_msg1))?)
 }
 
 #[wasm_bindgen]
@@ -157,6 +185,8 @@ pub fn li17_p2_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
         composite_dlog_proof,
         paillier_ek,
         paillier_encrypted_share,
+        blum_proof,
+        no_small_factor_proof,
     ) = msg;
 
     party_two::KeyGenSecondMsg::verify_commitments_and_dlog_proof(
@@ -173,7 +203,14 @@ pub fn li17_p2_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
         correct_key_proof,
         &party_two_paillier.ek,
     )
-    .expect("bad paillier key");
+    .map_err(|_| JsError::new("invalid paillier key proof"))?;
+
+    verify_paillier_modulus(
+        &party_two_paillier.ek,
+        &blum_proof,
+        &no_small_factor_proof,
+        &context.ring_pedersen,
+    )?;
 
     party_two::PaillierPublic::pdl_verify(
         &composite_dlog_proof,
@@ -182,7 +219,7 @@ pub fn li17_p2_key_gen2(context: JsValue, msg: JsValue) -> Result<JsValue, JsErr
         &party_two_paillier,
         &party_one_second_message.comm_witness.public_share,
     )
-    .expect("PDL error");
+    .map_err(|_| JsError::new("invalid pdl proof"))?;
 
     let party_two_private = party_two::Party2Private::set_private_key(&context.p2_ec_key_pair);
     let public_key = party_two::compute_pubkey(
@@ -6,7 +6,10 @@ use curv::{
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_two;
 use serde::{de, Deserialize, Serialize, Serializer};
 
+mod error;
 pub mod keygen;
+#[path = "../../../lindell17/src/modulus.rs"]
+pub mod modulus;
 pub mod sign;
 
 #[derive(Debug, Serialize, Deserialize)]
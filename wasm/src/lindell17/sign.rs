@@ -5,40 +5,85 @@ use gloo_utils::format::JsValueSerdeExt;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::party_one::SignatureRecid;
 use multi_party_ecdsa::protocols::two_party_ecdsa::lindell_2017::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::{JsError, JsValue};
 
 use super::keygen::{Li17SignP1Context, Li17SignP2Context};
 
+// Unlike the native crate, the P1 context here has no taint flag, because it
+// is kept by the JS caller. When `li17_p1_sign2` fails with "verify failed",
+// P2 may be running a selective-failure attack on P1's share: the caller must
+// record that for the key and refuse to sign or refresh it again, and move the
+// funds to a key from a fresh keygen.
+
 #[derive(Serialize, Deserialize)]
 pub struct Li17SignP2Context1 {
     pub public: Point<Secp256k1>,
     pub p2_private: party_two::Party2Private,
     pub p2_paillier_public: party_two::PaillierPublic,
+    session_id: Vec<u8>,
     hash: BigInt,
     p2_eph_comm_witness: party_two::EphCommWitness,
     p2_eph_ec_key_pair: party_two::EphEcKeyPair,
 }
 
-pub type Li17SignP2Msg1 = party_two::EphKeyGenFirstMsg;
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17SignP2Msg1 {
+    pub session_id: Vec<u8>,
+    pub hash_commitment: Vec<u8>,
+    pub eph_msg: party_two::EphKeyGenFirstMsg,
+}
 
-pub type Li17SignP2Msg2 = (party_two::PartialSig, party_two::EphKeyGenSecondMsg);
+#[derive(Serialize, Deserialize)]
+pub struct Li17SignP2Msg2 {
+    pub session_id: Vec<u8>,
+    pub partial_sig: party_two::PartialSig,
+    pub eph_msg: party_two::EphKeyGenSecondMsg,
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Li17SignP1Context1 {
     pub public: Point<Secp256k1>,
     pub p1_private: party_one::Party1Private,
+    session_id: Vec<u8>,
     hash: BigInt,
     p1_eph_ec_key_pair: party_one::EphEcKeyPair,
-    p1_msg1_from_p2: Li17SignP2Msg1,
+    p1_msg1_from_p2: party_two::EphKeyGenFirstMsg,
 }
 
-pub type Li17SignP1Msg1 = party_one::EphKeyGenFirstMsg;
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Li17SignP1Msg1 {
+    pub session_id: Vec<u8>,
+    pub eph_msg: party_one::EphKeyGenFirstMsg,
+}
+
+/// Same as the native `hash_commitment`, so wasm and native parties can sign together.
+fn hash_commitment(session_id: &[u8], message_hash: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(b"li17 sign");
+    hasher.update((session_id.len() as u64).to_be_bytes());
+    hasher.update(session_id);
+    hasher.update(message_hash);
+    hasher.finalize().to_vec()
+}
+
+fn check_session_id(expected: &[u8], received: &[u8]) -> Result<(), JsError> {
+    if expected != received {
+        return Err(JsError::new("session id mismatch"));
+    }
+    Ok(())
+}
 
 #[wasm_bindgen]
-pub fn li17_p2_sign1(context: JsValue, message_hash: JsValue) -> Result<JsValue, JsError> {
+pub fn li17_p2_sign1(
+    context: JsValue,
+    message_hash: JsValue,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
     let context: Li17SignP2Context = context.into_serde()?;
     let message_hash: Vec<u8> = message_hash.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
     let (eph_party_two_first_message, eph_comm_witness, eph_ec_key_pair_party2) =
         party_two::EphKeyGenFirstMsg::create_commitments();
 
@@ -46,35 +91,43 @@ pub fn li17_p2_sign1(context: JsValue, message_hash: JsValue) -> Result<JsValue,
         public: context.public,
         p2_private: context.p2_private,
         p2_paillier_public: context.p2_paillier_public,
+        session_id: session_id.clone(),
         hash: BigInt::from_bytes(&message_hash),
         p2_eph_comm_witness: eph_comm_witness,
         p2_eph_ec_key_pair: eph_ec_key_pair_party2,
     };
-    Ok(JsValue::from_serde(&(
-        context1,
-        eph_party_two_first_message,
-    ))?)
+    let msg1 = Li17SignP2Msg1 {
+        hash_commitment: hash_commitment(&session_id, &message_hash),
+        session_id,
+        eph_msg: eph_party_two_first_message,
+    };
+    Ok(JsValue::from_serde(&(context1, msg1))?)
 }
 
 #[wasm_bindgen]
 pub fn li17_p2_sign2(context: JsValue, msg: JsValue) -> Result<JsValue, JsError> {
     let context: Li17SignP2Context1 = context.into_serde()?;
     let msg: Li17SignP1Msg1 = msg.into_serde()?;
-    let eph_party_two_second_message =
-        party_two::EphKeyGenSecondMsg::verify_and_decommit(context.p2_eph_comm_witness, &msg)?;
+    check_session_id(&context.session_id, &msg.session_id)?;
+
+    let eph_party_two_second_message = party_two::EphKeyGenSecondMsg::verify_and_decommit(
+        context.p2_eph_comm_witness,
+        &msg.eph_msg,
+    )?;
 
     let partial_sig = party_two::PartialSig::compute(
         &context.p2_paillier_public.ek,
         &context.p2_paillier_public.encrypted_secret_share,
         &context.p2_private,
         &context.p2_eph_ec_key_pair,
-        &msg.public_share,
+        &msg.eph_msg.public_share,
         &context.hash,
     );
-    Ok(JsValue::from_serde(&(
+    Ok(JsValue::from_serde(&Li17SignP2Msg2 {
+        session_id: context.session_id,
         partial_sig,
-        eph_party_two_second_message,
-    ))?)
+        eph_msg: eph_party_two_second_message,
+    })?)
 }
 
 #[wasm_bindgen]
@@ -82,42 +135,51 @@ pub fn li17_p1_sign1(
     context: JsValue,
     msg: JsValue,
     message_hash: JsValue,
+    session_id: JsValue,
 ) -> Result<JsValue, JsError> {
     let context: Li17SignP1Context = context.into_serde()?;
     let msg: Li17SignP2Msg1 = msg.into_serde()?;
     let message_hash: Vec<u8> = message_hash.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    check_session_id(&session_id, &msg.session_id)?;
+    if msg.hash_commitment != hash_commitment(&session_id, &message_hash) {
+        return Err(JsError::new("message hash mismatch"));
+    }
 
     let (eph_party_one_first_message, eph_ec_key_pair_party1) =
         party_one::EphKeyGenFirstMsg::create();
     let context2 = Li17SignP1Context1 {
         public: context.public,
         p1_private: context.p1_private,
+        session_id: session_id.clone(),
         hash: BigInt::from_bytes(&message_hash),
         p1_eph_ec_key_pair: eph_ec_key_pair_party1,
-        p1_msg1_from_p2: msg,
+        p1_msg1_from_p2: msg.eph_msg,
+    };
+    let msg1 = Li17SignP1Msg1 {
+        session_id,
+        eph_msg: eph_party_one_first_message,
     };
-    Ok(JsValue::from_serde(&(
-        context2,
-        eph_party_one_first_message,
-    ))?)
+    Ok(JsValue::from_serde(&(context2, msg1))?)
 }
 
 #[wasm_bindgen]
 pub fn li17_p1_sign2(context: JsValue, msg: JsValue) -> Result<JsValue, JsError> {
     let context: Li17SignP1Context1 = context.into_serde()?;
     let msg: Li17SignP2Msg2 = msg.into_serde()?;
-    let (partial_sig, eph_party_two_second_message) = msg;
+    check_session_id(&context.session_id, &msg.session_id)?;
 
     party_one::EphKeyGenSecondMsg::verify_commitments_and_dlog_proof(
         &context.p1_msg1_from_p2,
-        &eph_party_two_second_message,
+        &msg.eph_msg,
     )?;
 
     let sig = party_one::Signature::compute_with_recid(
         &context.p1_private,
-        &partial_sig.c3,
+        &msg.partial_sig.c3,
         &context.p1_eph_ec_key_pair,
-        &eph_party_two_second_message.comm_witness.public_share,
+        &msg.eph_msg.comm_witness.public_share,
     );
 
     verify(&sig, &context.public, &context.hash)?;
@@ -128,7 +190,8 @@ pub fn li17_p1_sign2(context: JsValue, msg: JsValue) -> Result<JsValue, JsError>
 pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> Result<(), JsError> {
     let r = Scalar::<Secp256k1>::from(&sig.r);
     let s = Scalar::<Secp256k1>::from(&sig.s);
-    let b = s.invert().unwrap();
+    // s = 0 can be forced by the other party, and must not panic
+    let b = s.invert().ok_or_else(|| JsError::new("verify failed"))?;
     let a = Scalar::<Secp256k1>::from(message);
     let u1 = a * &b;
     let u2 = &r * &b;
@@ -137,12 +200,10 @@ pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> R
     let gu1 = g * u1;
     let yu2 = y * &u2;
     // can be faster using shamir trick
-    if r == Scalar::<Secp256k1>::from(
-        &(gu1 + yu2)
-            .x_coord()
-            .unwrap()
-            .mod_floor(Scalar::<Secp256k1>::group_order()),
-    ) {
+    let x = (gu1 + yu2)
+        .x_coord()
+        .ok_or_else(|| JsError::new("verify failed"))?;
+    if r == Scalar::<Secp256k1>::from(&x.mod_floor(Scalar::<Secp256k1>::group_order())) {
         Ok(())
     } else {
         Err(JsError::new("verify failed"))
@@ -0,0 +1,113 @@
+use gloo_utils::format::JsValueSerdeExt;
+use serde::{Deserialize, Serialize};
+use tss_eddsa::{
+    channel::*,
+    curv::elliptic::curves::{Ed25519, Point},
+};
+use wasm_bindgen::prelude::*;
+
+// Any protocol message, as JSON, so that `open` can check the sender and
+// receiver it names.
+#[derive(Serialize, Deserialize)]
+struct AnyMsg {
+    sender: u16,
+    #[serde(flatten)]
+    rest: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SenderMsg for AnyMsg {
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct AnyP2pMsg {
+    sender: u16,
+    receiver: u16,
+    #[serde(flatten)]
+    rest: serde_json::Map<String, serde_json::Value>,
+}
+
+impl SenderMsg for AnyP2pMsg {
+    fn sender(&self) -> u16 {
+        self.sender
+    }
+}
+
+impl P2pMsg for AnyP2pMsg {
+    fn receiver(&self) -> u16 {
+        self.receiver
+    }
+}
+
+#[wasm_bindgen]
+pub fn eddsa_identity_key_create() -> Result<JsValue, JsError> {
+    Ok(JsValue::from_serde(&IdentityKey::create())?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_seal(
+    identity: JsValue,
+    sender: u16,
+    receiver: u16,
+    receiver_pk: JsValue,
+    label: JsValue,
+    msg: JsValue,
+) -> Result<JsValue, JsError> {
+    let identity: IdentityKey = identity.into_serde()?;
+    let receiver_pk: Point<Ed25519> = receiver_pk.into_serde()?;
+    let label: Vec<u8> = label.into_serde()?;
+    let msg: serde_json::Value = msg.into_serde()?;
+
+    let sealed =
+        tss_eddsa::channel::seal(&identity, sender, receiver, &receiver_pk, &label, &msg)?;
+    Ok(JsValue::from_serde(&sealed)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_open(
+    identity: JsValue,
+    receiver: u16,
+    sender_pk: JsValue,
+    label: JsValue,
+    sealed: JsValue,
+) -> Result<JsValue, JsError> {
+    let identity: IdentityKey = identity.into_serde()?;
+    let sender_pk: Point<Ed25519> = sender_pk.into_serde()?;
+    let label: Vec<u8> = label.into_serde()?;
+    let sealed: SealedMsg = sealed.into_serde()?;
+
+    let msg: AnyP2pMsg =
+        tss_eddsa::channel::open(&identity, receiver, &sender_pk, &label, &sealed)?;
+    Ok(JsValue::from_serde(&msg)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_sign_broadcast(
+    identity: JsValue,
+    sender: u16,
+    label: JsValue,
+    msg: JsValue,
+) -> Result<JsValue, JsError> {
+    let identity: IdentityKey = identity.into_serde()?;
+    let label: Vec<u8> = label.into_serde()?;
+    let msg: serde_json::Value = msg.into_serde()?;
+
+    let signed = tss_eddsa::channel::sign_broadcast(&identity, sender, &label, &msg)?;
+    Ok(JsValue::from_serde(&signed)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_open_broadcast(
+    sender_pk: JsValue,
+    label: JsValue,
+    signed: JsValue,
+) -> Result<JsValue, JsError> {
+    let sender_pk: Point<Ed25519> = sender_pk.into_serde()?;
+    let label: Vec<u8> = label.into_serde()?;
+    let signed: SignedMsg = signed.into_serde()?;
+
+    let msg: AnyMsg = tss_eddsa::channel::open_broadcast(&sender_pk, &label, &signed)?;
+    Ok(JsValue::from_serde(&msg)?)
+}
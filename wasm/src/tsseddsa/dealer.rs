@@ -0,0 +1,28 @@
+use std::convert::TryInto;
+
+use gloo_utils::format::JsValueSerdeExt;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn eddsa_dealer_split_seed(seed: JsValue, t: u16, n: u16) -> Result<JsValue, JsError> {
+    let seed: Vec<u8> = seed.into_serde()?;
+    let seed: [u8; 32] = seed
+        .as_slice()
+        .try_into()
+        .map_err(|_| JsError::new("seed must be 32 bytes"))?;
+
+    let sign_keys = tss_eddsa::dealer::dealer_split_seed(&seed, t, n)?;
+    Ok(JsValue::from_serde(&sign_keys)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_dealer_split_expanded(expanded: JsValue, t: u16, n: u16) -> Result<JsValue, JsError> {
+    let expanded: Vec<u8> = expanded.into_serde()?;
+    let expanded: [u8; 64] = expanded
+        .as_slice()
+        .try_into()
+        .map_err(|_| JsError::new("expanded secret key must be 64 bytes"))?;
+
+    let sign_keys = tss_eddsa::dealer::dealer_split_expanded(&expanded, t, n)?;
+    Ok(JsValue::from_serde(&sign_keys)?)
+}
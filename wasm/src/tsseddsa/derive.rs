@@ -0,0 +1,46 @@
+use std::convert::TryInto;
+
+use gloo_utils::format::JsValueSerdeExt;
+use tss_eddsa::{
+    curv::elliptic::curves::{Ed25519, Point},
+    keygen::SignKey,
+};
+use wasm_bindgen::prelude::*;
+
+fn chain_code_from(chain_code: JsValue) -> Result<[u8; 32], JsError> {
+    let chain_code: Vec<u8> = chain_code.into_serde()?;
+    chain_code
+        .as_slice()
+        .try_into()
+        .map_err(|_| JsError::new("chain code must be 32 bytes"))
+}
+
+#[wasm_bindgen]
+pub fn eddsa_derive_pubkey(
+    agg_pubkey: JsValue,
+    chain_code: JsValue,
+    path: JsValue,
+) -> Result<JsValue, JsError> {
+    let agg_pubkey: Point<Ed25519> = agg_pubkey.into_serde()?;
+    let chain_code = chain_code_from(chain_code)?;
+    let path: Vec<u32> = path.into_serde()?;
+
+    let (child_pubkey, child_chain_code) =
+        tss_eddsa::derive::derive_pubkey(&agg_pubkey, &chain_code, &path)?;
+    Ok(JsValue::from_serde(&(child_pubkey, child_chain_code))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_derive_sign_key(
+    sign_key: JsValue,
+    chain_code: JsValue,
+    path: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let chain_code = chain_code_from(chain_code)?;
+    let path: Vec<u32> = path.into_serde()?;
+
+    let (child, child_chain_code) =
+        tss_eddsa::derive::derive_sign_key(&sign_key, &chain_code, &path)?;
+    Ok(JsValue::from_serde(&(child, child_chain_code))?)
+}
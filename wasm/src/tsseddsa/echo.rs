@@ -0,0 +1,72 @@
+use gloo_utils::format::JsValueSerdeExt;
+use tss_eddsa::{
+    echo::*,
+    keygen::{KeyGenComplaintMsg1, KeyGenComplaintMsg2, KeyGenMsg1, KeyGenMsg2},
+    sign::{SignMsg1, SignMsg2},
+};
+use wasm_bindgen::prelude::*;
+
+fn echo_phase1_for<M: BroadcastMsg + serde::de::DeserializeOwned>(
+    party_index: u16,
+    msgs: JsValue,
+) -> Result<JsValue, JsError> {
+    let msgs: Vec<M> = msgs.into_serde()?;
+    let echo = tss_eddsa::echo::echo_phase1(party_index, &msgs)?;
+    Ok(JsValue::from_serde(&echo)?)
+}
+
+fn echo_phase2_for<M: BroadcastMsg + serde::de::DeserializeOwned>(
+    parties: JsValue,
+    msgs: JsValue,
+    echoes: JsValue,
+) -> Result<(), JsError> {
+    let parties: Vec<u16> = parties.into_serde()?;
+    let msgs: Vec<M> = msgs.into_serde()?;
+    let echoes: Vec<EchoMsg> = echoes.into_serde()?;
+    tss_eddsa::echo::echo_phase2(&parties, &msgs, &echoes)?;
+    Ok(())
+}
+
+// only accused dealers need to answer, so the answers that arrived are echoed
+fn echo_phase2_answers(parties: JsValue, msgs: JsValue, echoes: JsValue) -> Result<(), JsError> {
+    let parties: Vec<u16> = parties.into_serde()?;
+    let msgs: Vec<KeyGenComplaintMsg2> = msgs.into_serde()?;
+    let echoes: Vec<EchoMsg> = echoes.into_serde()?;
+    let senders: Vec<_> = msgs.iter().map(|msg| msg.sender).collect();
+    tss_eddsa::echo::echo_phase2_from(&senders, &parties, &msgs, &echoes)?;
+    Ok(())
+}
+
+/// `round` is one of "keygen1", "keygen2", "complaint1", "complaint2", "sign1"
+/// or "sign2".
+#[wasm_bindgen]
+pub fn eddsa_echo_phase1(party_index: u16, round: &str, msgs: JsValue) -> Result<JsValue, JsError> {
+    match round {
+        KeyGenMsg1::ROUND => echo_phase1_for::<KeyGenMsg1>(party_index, msgs),
+        KeyGenMsg2::ROUND => echo_phase1_for::<KeyGenMsg2>(party_index, msgs),
+        KeyGenComplaintMsg1::ROUND => echo_phase1_for::<KeyGenComplaintMsg1>(party_index, msgs),
+        KeyGenComplaintMsg2::ROUND => echo_phase1_for::<KeyGenComplaintMsg2>(party_index, msgs),
+        SignMsg1::ROUND => echo_phase1_for::<SignMsg1>(party_index, msgs),
+        SignMsg2::ROUND => echo_phase1_for::<SignMsg2>(party_index, msgs),
+        _ => Err(JsError::new("unknown round")),
+    }
+}
+
+/// Echoes must be received over an authenticated channel, see `eddsa_sign_broadcast`.
+#[wasm_bindgen]
+pub fn eddsa_echo_phase2(
+    round: &str,
+    parties: JsValue,
+    msgs: JsValue,
+    echoes: JsValue,
+) -> Result<(), JsError> {
+    match round {
+        KeyGenMsg1::ROUND => echo_phase2_for::<KeyGenMsg1>(parties, msgs, echoes),
+        KeyGenMsg2::ROUND => echo_phase2_for::<KeyGenMsg2>(parties, msgs, echoes),
+        KeyGenComplaintMsg1::ROUND => echo_phase2_for::<KeyGenComplaintMsg1>(parties, msgs, echoes),
+        KeyGenComplaintMsg2::ROUND => echo_phase2_answers(parties, msgs, echoes),
+        SignMsg1::ROUND => echo_phase2_for::<SignMsg1>(parties, msgs, echoes),
+        SignMsg2::ROUND => echo_phase2_for::<SignMsg2>(parties, msgs, echoes),
+        _ => Err(JsError::new("unknown round")),
+    }
+}
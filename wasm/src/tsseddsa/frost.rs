@@ -0,0 +1,90 @@
+use gloo_utils::format::JsValueSerdeExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tss_eddsa::{frost::*, keygen::SignKey};
+use wasm_bindgen::prelude::*;
+
+// The context lives on the JS side, so nothing here can stop a caller from
+// passing an old context again, and signing a second message with the same
+// nonce reveals the key share. The wasm API therefore commits to a single
+// nonce at a time, bound to the message and signer set it is meant for, and
+// `eddsa_frost_phase2` refuses anything else.
+#[derive(Serialize, Deserialize)]
+struct BoundContext1 {
+    message_hash: [u8; 32],
+    signers: Vec<u16>,
+    context1: FrostContext1,
+}
+
+fn message_hash(message: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain(b"tss-wasm frost")
+        .chain(message)
+        .finalize()
+        .into()
+}
+
+/// Commits to one nonce for signing `message` with `signers`.
+#[wasm_bindgen]
+pub fn eddsa_frost_phase1(
+    sign_key: JsValue,
+    message: JsValue,
+    signers: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let message: Vec<u8> = message.into_serde()?;
+    let mut signers: Vec<u16> = signers.into_serde()?;
+    signers.sort();
+    signers.dedup();
+
+    let (context1, msg1) = tss_eddsa::frost::frost_phase1(&sign_key, 1)?;
+    let context1 = BoundContext1 {
+        message_hash: message_hash(&message),
+        signers,
+        context1,
+    };
+    Ok(JsValue::from_serde(&(context1, msg1))?)
+}
+
+/// Returns the share. The context must be deleted before the share is sent,
+/// and never restored from a backup.
+#[wasm_bindgen]
+pub fn eddsa_frost_phase2(
+    sign_key: JsValue,
+    context1: JsValue,
+    message: JsValue,
+    commitments: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let mut context1: BoundContext1 = context1.into_serde()?;
+    let message: Vec<u8> = message.into_serde()?;
+    let commitments: Vec<FrostCommitment> = commitments.into_serde()?;
+
+    let mut signers: Vec<_> = commitments.iter().map(|c| c.sender).collect();
+    signers.sort();
+    if context1.message_hash != message_hash(&message) || context1.signers != signers {
+        return Err(JsError::new(
+            "nonce was committed for another message or signer set",
+        ));
+    }
+
+    let msg2 =
+        tss_eddsa::frost::frost_phase2(&sign_key, &mut context1.context1, &message, commitments)?;
+    Ok(JsValue::from_serde(&msg2)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_frost_phase3(
+    sign_key: JsValue,
+    message: JsValue,
+    commitments: JsValue,
+    msgs2: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let message: Vec<u8> = message.into_serde()?;
+    let commitments: Vec<FrostCommitment> = commitments.into_serde()?;
+    let msgs2: Vec<FrostMsg2> = msgs2.into_serde()?;
+
+    let signature = tss_eddsa::frost::frost_phase3(&sign_key, &message, commitments, msgs2)?;
+    Ok(JsValue::from_serde(&signature)?)
+}
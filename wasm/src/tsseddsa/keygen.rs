@@ -3,8 +3,15 @@ use tss_eddsa::keygen::*;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-pub fn eddsa_keygen_phase1(t: u16, n: u16, party_index: u16) -> Result<JsValue, JsError> {
-    let (context1, msg1) = tss_eddsa::keygen::keygen_phase1(t, n, party_index)?;
+pub fn eddsa_keygen_phase1(
+    t: u16,
+    n: u16,
+    party_index: u16,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (context1, msg1) = tss_eddsa::keygen::keygen_phase1(t, n, party_index, &session_id)?;
     Ok(JsValue::from_serde(&(context1, msg1))?)
 }
 
@@ -33,3 +40,42 @@ pub fn eddsa_keygen_phase4(context3: JsValue, msgs3: JsValue) -> Result<JsValue,
     let sign_key = tss_eddsa::keygen::keygen_phase4(context3, msgs3)?;
     Ok(JsValue::from_serde(&sign_key)?)
 }
+
+#[wasm_bindgen]
+pub fn eddsa_keygen_complaint_phase1(
+    context3: JsValue,
+    msgs3: JsValue,
+) -> Result<JsValue, JsError> {
+    let context3: KeyGenContext3 = context3.into_serde()?;
+    let msgs3: Vec<KeyGenMsg3> = msgs3.into_serde()?;
+    let msg1 = tss_eddsa::keygen::keygen_complaint_phase1(&context3, &msgs3)?;
+    Ok(JsValue::from_serde(&msg1)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_keygen_complaint_phase2(
+    context3: JsValue,
+    sent_msgs3: JsValue,
+    msgs1: JsValue,
+) -> Result<JsValue, JsError> {
+    let context3: KeyGenContext3 = context3.into_serde()?;
+    let sent_msgs3: Vec<KeyGenMsg3> = sent_msgs3.into_serde()?;
+    let msgs1: Vec<KeyGenComplaintMsg1> = msgs1.into_serde()?;
+    let msg2 = tss_eddsa::keygen::keygen_complaint_phase2(&context3, &sent_msgs3, &msgs1)?;
+    Ok(JsValue::from_serde(&msg2)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_keygen_complaint_phase3(
+    context3: JsValue,
+    msgs3: JsValue,
+    msgs1: JsValue,
+    msgs2: JsValue,
+) -> Result<JsValue, JsError> {
+    let context3: KeyGenContext3 = context3.into_serde()?;
+    let msgs3: Vec<KeyGenMsg3> = msgs3.into_serde()?;
+    let msgs1: Vec<KeyGenComplaintMsg1> = msgs1.into_serde()?;
+    let msgs2: Vec<KeyGenComplaintMsg2> = msgs2.into_serde()?;
+    let sign_key = tss_eddsa::keygen::keygen_complaint_phase3(context3, msgs3, msgs1, msgs2)?;
+    Ok(JsValue::from_serde(&sign_key)?)
+}
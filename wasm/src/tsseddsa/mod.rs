@@ -1,2 +1,10 @@
+pub mod channel;
+pub mod dealer;
+pub mod derive;
+pub mod echo;
+pub mod frost;
 pub mod keygen;
+pub mod recover;
+pub mod refresh;
+pub mod reshare;
 pub mod sign;
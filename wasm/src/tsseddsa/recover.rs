@@ -0,0 +1,38 @@
+use gloo_utils::format::JsValueSerdeExt;
+use tss_eddsa::{keygen::SignKey, recover::*};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn eddsa_recover_phase1(
+    sign_key: JsValue,
+    helpers: JsValue,
+    lost_index: u16,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let helpers: Vec<u16> = helpers.into_serde()?;
+
+    let (context1, msgs1) = tss_eddsa::recover::recover_phase1(&sign_key, helpers, lost_index)?;
+    Ok(JsValue::from_serde(&(context1, msgs1))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_recover_phase2(
+    sign_key: JsValue,
+    context1: JsValue,
+    msgs1: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let context1: RecoverContext1 = context1.into_serde()?;
+    let msgs1: Vec<RecoverMsg1> = msgs1.into_serde()?;
+
+    let msg2 = tss_eddsa::recover::recover_phase2(&sign_key, context1, msgs1)?;
+    Ok(JsValue::from_serde(&msg2)?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_recover_phase3(party_index: u16, msgs2: JsValue) -> Result<JsValue, JsError> {
+    let msgs2: Vec<RecoverMsg2> = msgs2.into_serde()?;
+
+    let sign_key = tss_eddsa::recover::recover_phase3(party_index, msgs2)?;
+    Ok(JsValue::from_serde(&sign_key)?)
+}
@@ -0,0 +1,42 @@
+use gloo_utils::format::JsValueSerdeExt;
+use tss_eddsa::{echo::EchoMsg, keygen::SignKey, refresh::*};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn eddsa_refresh_phase1(sign_key: JsValue, session_id: JsValue) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (context1, msg1, msgs2) = tss_eddsa::refresh::refresh_phase1(&sign_key, &session_id)?;
+    Ok(JsValue::from_serde(&(context1, msg1, msgs2))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_refresh_phase2(
+    sign_key: JsValue,
+    context1: JsValue,
+    msgs1: JsValue,
+    msgs2: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let context1: RefreshContext1 = context1.into_serde()?;
+    let msgs1: Vec<RefreshMsg1> = msgs1.into_serde()?;
+    let msgs2: Vec<RefreshMsg2> = msgs2.into_serde()?;
+
+    let (context2, echo) = tss_eddsa::refresh::refresh_phase2(&sign_key, context1, msgs1, msgs2)?;
+    Ok(JsValue::from_serde(&(context2, echo))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_refresh_phase3(
+    sign_key: JsValue,
+    context2: JsValue,
+    echoes: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let context2: RefreshContext2 = context2.into_serde()?;
+    let echoes: Vec<EchoMsg> = echoes.into_serde()?;
+
+    let sign_key = tss_eddsa::refresh::refresh_phase3(&sign_key, context2, echoes)?;
+    Ok(JsValue::from_serde(&sign_key)?)
+}
@@ -0,0 +1,47 @@
+use gloo_utils::format::JsValueSerdeExt;
+use tss_eddsa::{echo::EchoMsg, keygen::SignKey, reshare::*};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub fn eddsa_reshare_phase1(
+    sign_key: JsValue,
+    old_parties: JsValue,
+    t: u16,
+    n: u16,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let old_parties: Vec<u16> = old_parties.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (msg1, msgs2) =
+        tss_eddsa::reshare::reshare_phase1(&sign_key, old_parties, t, n, &session_id)?;
+    Ok(JsValue::from_serde(&(msg1, msgs2))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_reshare_phase2(
+    t: u16,
+    n: u16,
+    party_index: u16,
+    session_id: JsValue,
+    msgs1: JsValue,
+    msgs2: JsValue,
+) -> Result<JsValue, JsError> {
+    let session_id: Vec<u8> = session_id.into_serde()?;
+    let msgs1: Vec<ReshareMsg1> = msgs1.into_serde()?;
+    let msgs2: Vec<ReshareMsg2> = msgs2.into_serde()?;
+
+    let (context2, echo) =
+        tss_eddsa::reshare::reshare_phase2(t, n, party_index, &session_id, msgs1, msgs2)?;
+    Ok(JsValue::from_serde(&(context2, echo))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_reshare_phase3(context2: JsValue, echoes: JsValue) -> Result<JsValue, JsError> {
+    let context2: ReshareContext2 = context2.into_serde()?;
+    let echoes: Vec<EchoMsg> = echoes.into_serde()?;
+
+    let sign_key = tss_eddsa::reshare::reshare_phase3(context2, echoes)?;
+    Ok(JsValue::from_serde(&sign_key)?)
+}
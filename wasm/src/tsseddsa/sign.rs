@@ -1,3 +1,5 @@
+use std::convert::TryInto;
+
 use gloo_utils::format::JsValueSerdeExt;
 use tss_eddsa::{keygen::SignKey, sign::*};
 use wasm_bindgen::prelude::*;
@@ -7,12 +9,77 @@ pub fn eddsa_sign_phase1(
     sign_key: JsValue,
     parties: JsValue,
     message: JsValue,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let parties: Vec<u16> = parties.into_serde()?;
+    let message: Vec<u8> = message.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (context1, msgs1) =
+        tss_eddsa::sign::sign_phase1(&sign_key, parties, &message, &session_id)?;
+    Ok(JsValue::from_serde(&(context1, msgs1))?)
+}
+
+/// Unsafe unless every co-signer is trusted, see
+/// `tss_eddsa::sign::sign_phase1_deterministic`.
+#[wasm_bindgen]
+pub fn eddsa_sign_phase1_deterministic(
+    sign_key: JsValue,
+    parties: JsValue,
+    message: JsValue,
+    session_id: JsValue,
 ) -> Result<JsValue, JsError> {
     let sign_key: SignKey = sign_key.into_serde()?;
     let parties: Vec<u16> = parties.into_serde()?;
     let message: Vec<u8> = message.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (context1, msgs1) =
+        tss_eddsa::sign::sign_phase1_deterministic(&sign_key, parties, &message, &session_id)?;
+    Ok(JsValue::from_serde(&(context1, msgs1))?)
+}
+
+#[wasm_bindgen]
+pub fn eddsa_sign_phase1_ctx(
+    sign_key: JsValue,
+    parties: JsValue,
+    message: JsValue,
+    context: JsValue,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let parties: Vec<u16> = parties.into_serde()?;
+    let message: Vec<u8> = message.into_serde()?;
+    let context: Vec<u8> = context.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
+
+    let (context1, msgs1) =
+        tss_eddsa::sign::sign_phase1_ctx(&sign_key, parties, &message, &context, &session_id)?;
+    Ok(JsValue::from_serde(&(context1, msgs1))?)
+}
+
+/// `digest` is the 64-byte SHA-512 hash of the data to sign.
+#[wasm_bindgen]
+pub fn eddsa_sign_phase1_ph(
+    sign_key: JsValue,
+    parties: JsValue,
+    digest: JsValue,
+    context: JsValue,
+    session_id: JsValue,
+) -> Result<JsValue, JsError> {
+    let sign_key: SignKey = sign_key.into_serde()?;
+    let parties: Vec<u16> = parties.into_serde()?;
+    let digest: Vec<u8> = digest.into_serde()?;
+    let digest: [u8; 64] = digest
+        .as_slice()
+        .try_into()
+        .map_err(|_| JsError::new("digest must be 64 bytes"))?;
+    let context: Vec<u8> = context.into_serde()?;
+    let session_id: Vec<u8> = session_id.into_serde()?;
 
-    let (context1, msgs1) = tss_eddsa::sign::sign_phase1(&sign_key, parties, &message)?;
+    let (context1, msgs1) =
+        tss_eddsa::sign::sign_phase1_ph(&sign_key, parties, &digest, &context, &session_id)?;
     Ok(JsValue::from_serde(&(context1, msgs1))?)
 }
 